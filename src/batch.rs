@@ -0,0 +1,216 @@
+// Batch runs over a directory of inputs, several per day: `dayNN.txt`, `dayNN-alice.txt`...
+//
+// The expected answers of `dayNN-alice.txt` are read from `dayNN-alice.answers`, part 1 on
+// the first line and part 2 on the second, a blank line leaving a part unchecked. Those of
+// `dayNN.txt` default to the known answers of `SOLUTIONS`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{check, no_solver, puzzle, trace};
+
+/// Input file found in the directory.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Input {
+    day: u32,
+    /// what follows `dayNN` in the name, "default" for `dayNN.txt`
+    label: String,
+    path: PathBuf,
+}
+
+/// Result of a solver on an input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+    /// Every expected answer was found, and there was at least one.
+    Correct,
+    Wrong,
+    /// No expected answer to compare to.
+    Unchecked,
+    /// Error or missing solver.
+    Failed,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Correct => "ok",
+            Outcome::Wrong => "wrong",
+            Outcome::Unchecked => "unchecked",
+            Outcome::Failed => "error",
+        }
+    }
+}
+
+/// Day and label of a file named `dayNN<label>.txt`.
+fn day_and_label(name: &str) -> Option<(u32, String)> {
+    let stem = name.strip_prefix("day")?.strip_suffix(".txt")?;
+    let digits = stem.get(..2)?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let label = stem[2..].trim_start_matches(['-', '_', '.']);
+    let label = if label.is_empty() { "default" } else { label };
+    Some((digits.parse().ok()?, label.to_string()))
+}
+
+/// Input files of `dir`, by day then label.
+fn discover(dir: &Path) -> Result<Vec<Input>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("cannot read {}: {e}", dir.display()))?;
+
+    let mut inputs = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if let Some((day, label)) = day_and_label(name) {
+            inputs.push(Input { day, label, path });
+        }
+    }
+
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// Expected answers of an input, empty if unknown.
+fn expected(input: &Input) -> (String, String) {
+    if let Ok(text) = fs::read_to_string(input.path.with_extension("answers")) {
+        let mut lines = text.lines().map(|line| line.trim().to_string());
+        return (
+            lines.next().unwrap_or_default(),
+            lines.next().unwrap_or_default(),
+        );
+    }
+
+    let default = format!("day{:02}.txt", input.day);
+    if input.path.file_name() != Some(default.as_ref()) {
+        return Default::default();
+    }
+    let (p1, p2) = crate::expected(input.day);
+    (p1.to_string(), p2.to_string())
+}
+
+fn run_input(input: &Input) -> Outcome {
+    println!("🎁 Day {} ({}): ", input.day, input.label);
+
+    let Some(puzzle) = puzzle(input.day) else {
+        eprintln!("error: {}", no_solver(input.day));
+        return Outcome::Failed;
+    };
+    let solved = fs::read_to_string(&input.path)
+        .map_err(|e| format!("cannot read {}: {e}", input.path.display()))
+        .and_then(|text| {
+            let _scope = trace::scope(input.day);
+            puzzle.solve_with_queries(&text)
+        });
+    let ((p1, p2), queries) = match solved {
+        Ok(solved) => solved,
+        Err(e) => {
+            eprintln!("error: day {} ({}): {e}", input.day, input.label);
+            return Outcome::Failed;
+        }
+    };
+    for answer in queries {
+        println!("  {answer}");
+    }
+
+    let (expected_p1, expected_p2) = expected(input);
+    let ok1 = check(1, &p1, &expected_p1);
+    let ok2 = check(2, &p2, &expected_p2);
+    if !(ok1 && ok2) {
+        Outcome::Wrong
+    } else if expected_p1.is_empty() && expected_p2.is_empty() {
+        Outcome::Unchecked
+    } else {
+        Outcome::Correct
+    }
+}
+
+/// Table of the outcomes, one row per day and one column per label.
+fn matrix(results: &[(Input, Outcome)]) -> String {
+    let mut labels: Vec<&str> = results.iter().map(|(i, _)| i.label.as_str()).collect();
+    labels.sort();
+    labels.dedup();
+    let mut days: Vec<u32> = results.iter().map(|(i, _)| i.day).collect();
+    days.dedup();
+
+    let width = |label: &str| label.len().max(Outcome::Unchecked.as_str().len());
+
+    let mut table = "Day".to_string();
+    for label in &labels {
+        table += &format!("  {label:<0$}", width(label));
+    }
+    for day in days {
+        table += &format!("\n{day:>3}");
+        for label in &labels {
+            let cell = results
+                .iter()
+                .find(|(i, _)| i.day == day && i.label == *label)
+                .map_or("-", |(_, outcome)| outcome.as_str());
+            table += &format!("  {cell:<0$}", width(label));
+        }
+    }
+    table
+        .lines()
+        .map(|line| line.trim_end().to_string() + "\n")
+        .collect()
+}
+
+/// Solves every input of `dir` and prints the matrix of the outcomes.
+/// Returns `false` if an answer is wrong or an input cannot be solved.
+pub fn run(dir: &Path) -> Result<bool, String> {
+    let inputs = discover(dir)?;
+    if inputs.is_empty() {
+        return Err(format!("no dayNN*.txt input in {}", dir.display()));
+    }
+
+    let mut results = Vec::new();
+    for input in inputs {
+        let outcome = run_input(&input);
+        println!();
+        results.push((input, outcome));
+    }
+
+    print!("{}", matrix(&results));
+
+    Ok(results
+        .iter()
+        .all(|(_, outcome)| matches!(outcome, Outcome::Correct | Outcome::Unchecked)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_day_and_label() {
+        assert_eq!(
+            day_and_label("day14.txt"),
+            Some((14, "default".to_string()))
+        );
+        assert_eq!(
+            day_and_label("day07-alice.txt"),
+            Some((7, "alice".to_string()))
+        );
+        assert_eq!(day_and_label("day21bob.txt"), Some((21, "bob".to_string())));
+        assert_eq!(day_and_label("day1.txt"), None);
+        assert_eq!(day_and_label("day14.answers"), None);
+        assert_eq!(day_and_label("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_matrix() {
+        let input = |day, label: &str| Input {
+            day,
+            label: label.to_string(),
+            path: PathBuf::new(),
+        };
+        let results = [
+            (input(14, "alice"), Outcome::Correct),
+            (input(14, "default"), Outcome::Wrong),
+            (input(21, "default"), Outcome::Failed),
+        ];
+        assert_eq!(
+            matrix(&results),
+            "Day  alice      default\n 14  ok         wrong\n 21  -          error\n"
+        );
+    }
+}
@@ -0,0 +1,434 @@
+// Arbitrary-precision unsigned integers, for answers that outgrow the machine integers.
+//
+// Numbers are vectors of 32-bit limbs, least significant first, so that the product of
+// two limbs plus two carries fits in a u64.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Rem};
+use std::str::FromStr;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    /// No trailing zero limb: zero has no limb at all.
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn from_limbs(mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        Self { limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Half rounded down, and whether `self` is odd.
+    pub fn halve(&self) -> (Self, bool) {
+        let (half, rem) = self.div_rem_small(2);
+        (half, rem == 1)
+    }
+
+    pub fn pow(&self, mut exp: u32) -> Self {
+        let mut result = Self::from(1u64);
+        let mut square = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = &result * &square;
+            }
+            exp >>= 1;
+            if exp > 0 {
+                square = &square * &square;
+            }
+        }
+        result
+    }
+
+    fn bits(&self) -> usize {
+        self.limbs.last().map_or(0, |top| {
+            32 * self.limbs.len() - top.leading_zeros() as usize
+        })
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.limbs[i / 32] >> (i % 32) & 1 == 1
+    }
+
+    /// self = self * factor + term, with factor > 0
+    fn mul_add_small(&mut self, factor: u32, term: u32) {
+        let mut carry = u64::from(term);
+        for limb in &mut self.limbs {
+            let t = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = t as u32;
+            carry = t >> 32;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    fn div_rem_small(&self, divisor: u32) -> (Self, u32) {
+        let mut rem = 0u64;
+        let mut quotient = vec![0; self.limbs.len()];
+        for (i, &limb) in self.limbs.iter().enumerate().rev() {
+            let cur = (rem << 32) | u64::from(limb);
+            quotient[i] = (cur / u64::from(divisor)) as u32;
+            rem = cur % u64::from(divisor);
+        }
+        (Self::from_limbs(quotient), rem as u32)
+    }
+
+    /// self -= other, with self >= other.
+    fn sub_assign(&mut self, other: &Self) {
+        let mut borrow = false;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let rhs = other.limbs.get(i).copied().unwrap_or(0);
+            if i >= other.limbs.len() && !borrow {
+                break;
+            }
+            let (d, b1) = limb.overflowing_sub(rhs);
+            let (d, b2) = d.overflowing_sub(u32::from(borrow));
+            *limb = d;
+            borrow = b1 || b2;
+        }
+        debug_assert!(!borrow, "subtraction underflow");
+        *self = Self::from_limbs(std::mem::take(&mut self.limbs));
+    }
+
+    /// `self - other`, or `None` if `other` is greater.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let mut difference = self.clone();
+        (*self >= *other).then(|| {
+            difference.sub_assign(other);
+            difference
+        })
+    }
+
+    /// Decimal digits, in full if they are short enough to be read, else the first and
+    /// last ones with their count.
+    pub fn abbreviated(&self) -> String {
+        const SHOWN: usize = 10;
+        let digits = self.to_string();
+        let len = digits.len();
+        if len <= 3 * SHOWN {
+            digits
+        } else {
+            let (first, last) = (&digits[..SHOWN], &digits[len - SHOWN..]);
+            format!("{first}...{last} ({len} digits)")
+        }
+    }
+
+    /// Quotient and remainder of the division by `divisor`, which must not be zero.
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        assert!(!divisor.is_zero(), "division by zero");
+
+        if let [small] = divisor.limbs[..] {
+            let (q, r) = self.div_rem_small(small);
+            return (q, Self::from(u64::from(r)));
+        }
+        if *self < *divisor {
+            return (Self::default(), self.clone());
+        }
+
+        // long division, one bit at a time
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut rem = Self::default();
+        for i in (0..self.bits()).rev() {
+            rem.mul_add_small(2, u32::from(self.bit(i)));
+            if rem >= *divisor {
+                rem.sub_assign(divisor);
+                quotient[i / 32] |= 1 << (i % 32);
+            }
+        }
+        (Self::from_limbs(quotient), rem)
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> Self {
+        Self::from(u128::from(n))
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        Self::from_limbs((0..4).map(|i| (n >> (32 * i)) as u32).collect())
+    }
+}
+
+impl TryFrom<&BigUint> for u128 {
+    type Error = String;
+
+    fn try_from(n: &BigUint) -> Result<Self, String> {
+        if n.limbs.len() > 4 {
+            return Err(format!("{n} does not fit in 128 bits"));
+        }
+        Ok(n.limbs
+            .iter()
+            .rev()
+            .fold(0, |acc, &limb| acc << 32 | u128::from(limb)))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0;
+        for i in 0..len {
+            let a = u64::from(self.limbs.get(i).copied().unwrap_or(0));
+            let b = u64::from(other.limbs.get(i).copied().unwrap_or(0));
+            let t = a + b + carry;
+            limbs.push(t as u32);
+            carry = t >> 32;
+        }
+        limbs.push(carry as u32);
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let t = u64::from(limbs[i + j]) + u64::from(a) * u64::from(b) + carry;
+                limbs[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::from_limbs(limbs)
+    }
+}
+
+impl Div<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn div(self, divisor: &BigUint) -> BigUint {
+        self.div_rem(divisor).0
+    }
+}
+
+impl Rem<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn rem(self, divisor: &BigUint) -> BigUint {
+        self.div_rem(divisor).1
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: BigUint) -> BigUint {
+        &self + &other
+    }
+}
+
+impl Mul for BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: BigUint) -> BigUint {
+        &self * &other
+    }
+}
+
+impl AddAssign for BigUint {
+    fn add_assign(&mut self, other: BigUint) {
+        *self = &*self + &other;
+    }
+}
+
+impl MulAssign for BigUint {
+    fn mul_assign(&mut self, other: BigUint) {
+        *self = &*self * &other;
+    }
+}
+
+impl Sum for BigUint {
+    fn sum<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::default(), |acc, n| acc + n)
+    }
+}
+
+impl Product for BigUint {
+    fn product<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::from(1u64), |acc, n| acc * n)
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = String;
+
+    /// Parses a decimal number.
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s.is_empty() {
+            return Err("empty number".to_string());
+        }
+        let mut n = BigUint::default();
+        for ch in s.chars() {
+            let digit = ch
+                .to_digit(10)
+                .ok_or_else(|| format!("invalid digit {ch:?} in {s:?}"))?;
+            n.mul_add_small(10, digit);
+        }
+        Ok(n)
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // groups of 9 digits, least significant first
+        const GROUP: u32 = 1_000_000_000;
+
+        let mut groups = Vec::new();
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (q, r) = n.div_rem_small(GROUP);
+            groups.push(r);
+            n = q;
+        }
+
+        let mut digits = groups.pop().unwrap_or(0).to_string();
+        for group in groups.iter().rev() {
+            digits += &format!("{group:09}");
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+/// Shows the decimal value rather than the limbs.
+impl fmt::Debug for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_format_and_parse() {
+        assert_eq!(BigUint::default().to_string(), "0");
+        assert_eq!(BigUint::from(u128::MAX).to_string(), u128::MAX.to_string());
+        assert_eq!(big("000123").to_string(), "123");
+        assert_eq!(format!("{:>5}", big("42")), "   42");
+        assert_eq!(
+            big("1000000000000000000000").to_string(),
+            "1000000000000000000000"
+        );
+
+        assert!("".parse::<BigUint>().is_err());
+        assert!("12a".parse::<BigUint>().is_err());
+        assert!("-1".parse::<BigUint>().is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let two128 = BigUint::from(u128::MAX) + BigUint::from(1u64);
+        assert_eq!(
+            (&two128 * &two128).to_string(),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+        );
+
+        let factorial: BigUint = (1u64..=30).map(BigUint::from).product();
+        assert_eq!(factorial.to_string(), "265252859812191058636308480000000");
+        assert_eq!(
+            (1u64..=4).map(BigUint::from).sum::<BigUint>(),
+            BigUint::from(10u64)
+        );
+
+        let (q, r) = factorial.div_rem(&two128);
+        assert_eq!(&(&q * &two128) + &r, factorial);
+        assert!(r < two128);
+
+        let x = big("123456789012345678901234567890");
+        let y = big("98765432109876543210");
+        let z = &(&x * &y) + &big("12345");
+        assert_eq!(&z / &y, x);
+        assert_eq!(&z % &y, big("12345"));
+        assert_eq!(&y / &x, BigUint::default());
+        assert!(y < x);
+
+        assert_eq!(big("12").pow(30), big("237376313799769806328950291431424"));
+        assert_eq!(x.pow(0), BigUint::from(1u64));
+        assert_eq!(big("12345").halve(), (big("6172"), true));
+        assert_eq!(BigUint::default().halve(), (BigUint::default(), false));
+        assert!(BigUint::default().is_zero());
+        assert_eq!(
+            u128::try_from(&big("1000000000000000000000")),
+            Ok(10u128.pow(21))
+        );
+        assert_eq!(u128::try_from(&BigUint::from(u128::MAX)), Ok(u128::MAX));
+        assert!(u128::try_from(&two128).is_err());
+        assert_eq!(
+            two128.checked_sub(&big("1")),
+            Some(BigUint::from(u128::MAX))
+        );
+        assert_eq!(x.checked_sub(&x), Some(BigUint::default()));
+        assert_eq!(y.checked_sub(&x), None);
+
+        assert_eq!(x.abbreviated(), "123456789012345678901234567890");
+        assert_eq!(
+            big("12").pow(30).pow(2).abbreviated(),
+            "5634751435...4894667776 (65 digits)"
+        );
+    }
+
+    #[test]
+    fn test_matches_u128() {
+        property::check(
+            |s| {
+                (
+                    s.int(0u64..=u64::MAX),
+                    s.int(0u64..=u64::MAX),
+                    s.int(1u64..=u64::MAX),
+                )
+            },
+            |&(a, b, c)| {
+                let wide = u128::from(a) * u128::from(b);
+                let (ba, bb, bc) = (BigUint::from(a), BigUint::from(b), BigUint::from(c));
+                let product = &ba * &bb;
+                let (q, r) = product.div_rem(&bc);
+
+                product.to_string() == wide.to_string()
+                    && product.to_string().parse() == Ok(product.clone())
+                    && (&ba + &bb).to_string() == (u128::from(a) + u128::from(b)).to_string()
+                    && ba.checked_sub(&bb) == a.checked_sub(b).map(BigUint::from)
+                    && q == BigUint::from(wide / u128::from(c))
+                    && r == BigUint::from(wide % u128::from(c))
+            },
+        );
+    }
+}
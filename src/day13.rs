@@ -1,143 +1,1482 @@
 // Day 13: Legume Plantation
 // https://lovemathboy.github.io/day13.html
+//
+// Besides `even`, `odd` and `all`, a spray may target the plants whose heights are in a
+// residue class, as `spray 1 mod 3`. Any moduli are accepted as long as they have a common
+// multiple that fits in 64 bits; a spray then costs a step for each residue modulo that
+// common multiple, among those of its class, that holds plants.
+//
+// The log may also contain commands reporting on the plantation under the rules of both
+// parts, whose answers are printed before those of the puzzle: `query <id>` gives the
+// height of the `<id>`th planted plant, `count <target>` the number of plants that a spray
+// would target, and `total` the sum of the heights.
+//
+// To explore variants of a log, `undo` cancels the last `plant` or `spray` that is still in
+// effect, `checkpoint <name>` names the current state and `rollback <name>` comes back to
+// it. Both simulations journal the changes that a later command cancels, so that going
+// back costs only the work that it cancels.
 
-use std::mem;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day13.txt").ok()?;
+use crate::parse::{self, Line};
 
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    solve_with_queries(input).map(|(answers, _)| answers)
 }
 
-fn part1(input: &str) -> u64 {
-    let mut even_heighs = 0;
-    let mut odd_heighs = 0;
+/// Answers of both parts, with those to the `query`, `count` and `total` commands of the
+/// log, from a single replay.
+pub fn solve_with_queries(input: &str) -> Result<((String, String), Vec<String>), String> {
+    let commands = parse_input(input)?;
+    let (part1, part2, answers) = replay(&commands);
+    Ok(((part1.to_string(), part2.to_string()), answers))
+}
 
-    let mut even_plants = 0;
-    let mut odd_plants = 0;
+/// Plants sprayed by a `spray` command, or counted by a `count` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Target {
+    All,
+    /// heights equal to `residue` modulo `modulus`
+    Residue {
+        residue: u64,
+        modulus: u64,
+    },
+}
 
-    for line in input.lines() {
-        let (a, b) = line.split_once(' ').unwrap();
+impl Target {
+    const EVEN: Target = Target::Residue {
+        residue: 0,
+        modulus: 2,
+    };
+    const ODD: Target = Target::Residue {
+        residue: 1,
+        modulus: 2,
+    };
 
-        if a == "plant" {
-            let height: u64 = b.parse().unwrap();
-            if height.is_multiple_of(2) {
-                even_heighs += height;
-                even_plants += 1;
-            } else {
-                odd_heighs += height;
-                odd_plants += 1;
-            }
-        } else if a == "spray" {
-            match b {
-                "all" => {
-                    even_heighs += even_plants;
-                    odd_heighs += odd_plants;
-
-                    mem::swap(&mut odd_heighs, &mut even_heighs);
-                    mem::swap(&mut odd_plants, &mut even_plants);
-                }
-                "even" => {
-                    odd_heighs += even_heighs + even_plants;
-                    odd_plants += even_plants;
-                    even_heighs = 0;
-                    even_plants = 0
-                }
-                "odd" => {
-                    even_heighs += odd_heighs + odd_plants;
-                    even_plants += odd_plants;
-                    odd_heighs = 0;
-                    odd_plants = 0;
+    /// Modulus and residue of the targeted heights, 1 and 0 for all of them.
+    fn class(self) -> (u64, u64) {
+        match self {
+            Target::All => (1, 0),
+            Target::Residue { residue, modulus } => (modulus, residue),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Target::All => write!(f, "all"),
+            Target::EVEN => write!(f, "even"),
+            Target::ODD => write!(f, "odd"),
+            Target::Residue { residue, modulus } => write!(f, "{residue} mod {modulus}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Command {
+    Plant(u64),
+    Spray(Target),
+    /// height of a plant, by its number starting from 1
+    Query(usize),
+    Count(Target),
+    Total,
+    Undo,
+    /// back to the state after this number of changes still in effect, the checkpoints
+    /// being resolved when parsing
+    Rollback(usize),
+}
+
+/// Least common multiple, `None` if it does not fit in 64 bits.
+fn lcm(a: u64, b: u64) -> Option<u64> {
+    fn gcd(a: u64, b: u64) -> u64 {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// Modulus of the target of a command, 1 if it has none.
+fn modulus(command: &Command) -> u64 {
+    match command {
+        Command::Spray(target) | Command::Count(target) => target.class().0,
+        _ => 1,
+    }
+}
+
+/// Moduli of the targets of a log, 1 included.
+fn moduli(commands: &[Command]) -> Vec<u64> {
+    let mut moduli: Vec<u64> = commands.iter().map(modulus).chain([1]).collect();
+    moduli.sort_unstable();
+    moduli.dedup();
+    moduli
+}
+
+/// `all`, `even`, `odd` or `<residue> mod <modulus>`.
+fn target(line: &mut Line) -> Result<Target, String> {
+    let at = *line;
+    let mut number = *line;
+    let Ok(residue) = number.int::<u64>() else {
+        return match line.word()? {
+            "all" => Ok(Target::All),
+            "even" => Ok(Target::EVEN),
+            "odd" => Ok(Target::ODD),
+            target if target.bytes().all(|b| b.is_ascii_digit()) => {
+                Err(at.error(format!("residue {target} out of range")))
+            }
+            target => Err(at.error(format!("unknown target {target:?}"))),
+        };
+    };
+    *line = number;
+    let keyword = *line;
+    if line.word() != Ok("mod") {
+        return Err(keyword.error("expected \"mod\" after the residue"));
+    }
+    let modulus = line.int()?;
+    if modulus == 0 {
+        return Err(at.error("modulus 0"));
+    }
+    if residue >= modulus {
+        return Err(at.error(format!("residue {residue} not below the modulus {modulus}")));
+    }
+    Ok(Target::Residue { residue, modulus })
+}
+
+fn parse_input(input: &str) -> Result<Vec<Command>, String> {
+    let mut commands = Vec::new();
+    // part 2 keeps the heights by residue modulo an even common multiple
+    let mut common = 2;
+    // changes in effect, true for a `plant`, with the bound after them
+    let mut changes: Vec<(bool, u64)> = Vec::new();
+    let mut plants = 0;
+    let mut checkpoints: HashMap<&str, usize> = HashMap::new();
+    // bound of the sum of the heights under the rules of part 1, which exceeds those of
+    // part 2 and of every plant
+    let mut bound: u64 = 0;
+    for mut line in parse::lines(input) {
+        let start = line;
+        let command = match line.word()? {
+            "plant" => Command::Plant(line.int()?),
+            "spray" => Command::Spray(target(&mut line)?),
+            "query" => {
+                let at = line;
+                let id = line.int()?;
+                if !(1..=plants).contains(&id) {
+                    return Err(at.error(format!("no plant #{id} yet")));
                 }
-                _ => {
-                    panic!("bad line {line}");
+                Command::Query(id)
+            }
+            "count" => Command::Count(target(&mut line)?),
+            "total" => Command::Total,
+            "undo" => {
+                if changes.is_empty() {
+                    return Err(start.error("nothing to undo"));
+                }
+                Command::Undo
+            }
+            "checkpoint" => {
+                checkpoints.insert(line.word()?, changes.len());
+                line.end()?;
+                continue;
+            }
+            "rollback" => {
+                let at = line;
+                let name = line.word()?;
+                let Some(&kept) = checkpoints.get(name) else {
+                    return Err(at.error(format!("no checkpoint {name:?}")));
+                };
+                Command::Rollback(kept)
+            }
+            verb => return Err(start.error(format!("unknown command {verb:?}"))),
+        };
+        line.end()?;
+        common = lcm(common, modulus(&command)).ok_or_else(|| {
+            start.error(format!(
+                "the moduli of the targets have no common multiple up to {}",
+                u64::MAX
+            ))
+        })?;
+
+        let growth = match command {
+            Command::Plant(height) => height,
+            Command::Spray(_) => plants as u64,
+            _ => 0,
+        };
+        bound = bound.checked_add(growth).ok_or_else(|| {
+            start.error(format!("the heights may add up to more than {}", u64::MAX))
+        })?;
+
+        let kept = match command {
+            Command::Plant(_) | Command::Spray(_) => {
+                changes.push((matches!(command, Command::Plant(_)), bound));
+                changes.len()
+            }
+            Command::Undo => changes.len() - 1,
+            Command::Rollback(kept) => kept,
+            _ => changes.len(),
+        };
+        plants -= changes.drain(kept..).filter(|&(plant, _)| plant).count();
+        plants += usize::from(matches!(command, Command::Plant(_)));
+        bound = changes.last().map_or(0, |&(_, bound)| bound);
+        // the changes that a checkpoint saw are gone
+        checkpoints.retain(|_, changes| *changes <= kept);
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+/// Applies the log under the rules of both parts. Returns the final sums of the heights,
+/// and the answers to the queries along the way.
+fn replay(commands: &[Command]) -> (u64, u64, Vec<String>) {
+    let moduli = moduli(commands);
+    let mut growing = Growing::new(&moduli);
+    let mut halving = Halving::new(&moduli);
+    let mut answers = Vec::new();
+    for (&command, &settled) in commands.iter().zip(&settled(commands)) {
+        growing.journal.settle(settled);
+        halving.journal.settle(settled);
+        match command {
+            Command::Plant(height) => {
+                growing.plant(height);
+                halving.plant(height);
+            }
+            Command::Spray(target) => {
+                growing.spray(target);
+                halving.spray(target);
+            }
+            Command::Query(id) => answers.push(format!(
+                "plant #{id}: {} (part 1), {} (part 2)",
+                growing.height(id - 1),
+                halving.height(id - 1)
+            )),
+            Command::Count(target) => answers.push(format!(
+                "{target} plants: {} (part 1), {} (part 2)",
+                growing.count(target),
+                halving.count(target)
+            )),
+            Command::Total => answers.push(format!(
+                "total: {} (part 1), {} (part 2)",
+                growing.total(),
+                halving.total()
+            )),
+            Command::Undo => {
+                growing.undo();
+                halving.undo();
+            }
+            Command::Rollback(kept) => {
+                while growing.changes() > kept {
+                    growing.undo();
+                    halving.undo();
                 }
             }
         }
     }
+    (growing.total(), halving.total(), answers)
+}
+
+/// Number of changes that stay in effect until the end of the log, from each command on:
+/// those that no later `undo` or `rollback` cancels.
+fn settled(commands: &[Command]) -> Vec<usize> {
+    let mut changes = 0;
+    let mut settled: Vec<usize> = commands
+        .iter()
+        .map(|command| {
+            match *command {
+                Command::Plant(_) | Command::Spray(_) => changes += 1,
+                Command::Undo => changes -= 1,
+                Command::Rollback(kept) => changes = kept,
+                _ => {}
+            }
+            changes
+        })
+        .collect();
+    for i in (1..settled.len()).rev() {
+        settled[i - 1] = settled[i - 1].min(settled[i]);
+    }
+    settled
+}
+
+/// Changes in effect, with what it takes to cancel those that a later command may cancel.
+struct Journal<T> {
+    /// number of changes in effect
+    len: usize,
+    /// changes that are never cancelled, from the first one
+    settled: usize,
+    /// what it takes to cancel the last changes in effect, after the settled ones
+    entries: VecDeque<T>,
+}
+
+impl<T> Journal<T> {
+    fn new() -> Self {
+        Self {
+            len: 0,
+            settled: 0,
+            entries: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, entry: T) {
+        self.len += 1;
+        if self.len > self.settled {
+            self.entries.push_back(entry);
+        }
+    }
+
+    /// Whether the next change will be journaled, for building its entry only then.
+    fn journals_next(&self) -> bool {
+        self.len >= self.settled
+    }
+
+    /// What it takes to cancel the last change in effect, which is not settled.
+    fn pop(&mut self) -> Option<T> {
+        self.len -= 1;
+        self.entries.pop_back()
+    }
+
+    /// Forgets how to cancel the first `changes` changes, which are never cancelled.
+    fn settle(&mut self, changes: usize) {
+        self.settled = changes;
+        let kept = self.len.saturating_sub(changes);
+        let forgotten = self.entries.len().saturating_sub(kept);
+        self.entries.drain(..forgotten);
+    }
+}
+
+/// Number of plants by residue class, for each modulus of the targets of a log.
+///
+/// The plants are kept in groups numbered by a residue modulo a multiple of every
+/// modulus, those of a group being in the same class for each modulus. The census lists
+/// the groups that are not empty in each class, so that a spray or a count only looks at
+/// those of its target. The tallies of a modulus catch up with the changes to the groups
+/// only when a command uses it, and are counted again from the groups once catching up
+/// would cost more.
+struct Census {
+    tallies: Vec<Tallies>,
+    /// changes from the `forgotten`th on, as the group with its plants before and after
+    changes: VecDeque<(u64, u64, u64)>,
+    forgotten: usize,
+    /// groups that are not empty
+    groups: usize,
+}
+
+/// Plants by residue class for one modulus.
+struct Tallies {
+    modulus: u64,
+    /// by residue, for the classes that are not empty
+    classes: HashMap<u64, Tally>,
+    /// number of changes caught up with, `None` if the classes are to be counted again
+    caught_up: Option<usize>,
+}
 
-    even_heighs + odd_heighs
+/// Plants of the groups of a residue class, and those of its groups that are not empty.
+#[derive(Default)]
+struct Tally {
+    plants: u64,
+    /// bit `j % 64` of word `j / 64` for group `j * modulus + residue`, without the words
+    /// equal to 0
+    groups: BTreeMap<u64, u64>,
 }
 
-fn part2(input: &str) -> u64 {
-    let mut evens = Vec::new();
-    let mut odds = Vec::new();
+impl Census {
+    fn new(moduli: &[u64]) -> Self {
+        let tallies = moduli
+            .iter()
+            .map(|&modulus| Tallies {
+                modulus,
+                classes: HashMap::new(),
+                caught_up: Some(0),
+            })
+            .collect();
+        Self {
+            tallies,
+            changes: VecDeque::new(),
+            forgotten: 0,
+            groups: 0,
+        }
+    }
+
+    /// Records that a group went from `from` plants to `to`.
+    fn update(&mut self, group: u64, from: u64, to: u64) {
+        if from != to {
+            self.groups = self.groups + usize::from(from == 0) - usize::from(to == 0);
+            self.changes.push_back((group, from, to));
+        }
+    }
+
+    /// Brings the tallies of `modulus`, one of the moduli, up to date, `groups` listing
+    /// the plants of the groups that are not empty.
+    fn catch_up<I>(&mut self, modulus: u64, groups: impl FnOnce() -> I)
+    where
+        I: Iterator<Item = (u64, u64)>,
+    {
+        // counting the groups again costs less than catching up with twice as many
+        // changes, and the changes that every tally has caught up with are forgotten
+        let changes = self.forgotten + self.changes.len();
+        let lag = 2 * self.groups.max(1024);
+        for tallies in &mut self.tallies {
+            if tallies
+                .caught_up
+                .is_some_and(|caught_up| changes - caught_up > lag)
+            {
+                tallies.caught_up = None;
+                tallies.classes = HashMap::new();
+            }
+        }
+        let oldest = self.tallies.iter().filter_map(|tallies| tallies.caught_up);
+        let oldest = oldest.min().unwrap_or(changes);
+        self.changes.drain(..oldest - self.forgotten);
+        self.forgotten = oldest;
+
+        let tallies = self
+            .tallies
+            .iter_mut()
+            .find(|tallies| tallies.modulus == modulus)
+            .unwrap();
+        match tallies.caught_up {
+            Some(caught_up) => {
+                for &(group, from, to) in self.changes.range(caught_up - self.forgotten..) {
+                    tallies.update(group, from, to);
+                }
+            }
+            None => {
+                for (group, plants) in groups() {
+                    tallies.update(group, 0, plants);
+                }
+            }
+        }
+        tallies.caught_up = Some(changes);
+    }
 
-    for line in input.lines() {
-        let (a, b) = line.split_once(' ').unwrap();
+    /// Tally of the groups equal to `residue` modulo `modulus`, as of its last catch-up.
+    fn tally(&self, modulus: u64, residue: u64) -> Option<&Tally> {
+        let tallies = self
+            .tallies
+            .iter()
+            .find(|tallies| tallies.modulus == modulus)
+            .unwrap();
+        tallies.classes.get(&residue)
+    }
 
-        if a == "plant" {
-            let height: u64 = b.parse().unwrap();
+    fn plants(&self, modulus: u64, residue: u64) -> u64 {
+        self.tally(modulus, residue).map_or(0, |tally| tally.plants)
+    }
 
-            if height.is_multiple_of(2) {
-                evens.push(height);
+    /// First group after `after` that is not empty and equal to `residue` modulo
+    /// `modulus`, as of its last catch-up.
+    fn next_group(&self, modulus: u64, residue: u64, after: Option<u64>) -> Option<u64> {
+        let groups = &self.tally(modulus, residue)?.groups;
+        let from = after.map_or(0, |after| after / modulus + 1);
+        groups.range(from / 64..).find_map(|(&word, &bits)| {
+            let bits = if word == from / 64 {
+                bits & (!0 << (from % 64))
             } else {
-                odds.push(height);
-            }
-        } else if a == "spray" {
-            let mut new_evens = Vec::new();
-            let mut new_odds = Vec::new();
-
-            if b == "all" || b == "even" {
-                let mut i = 0;
-                while i < evens.len() {
-                    evens[i] /= 2;
-                    if evens[i] == 0 {
-                        // height is 0, remove it
-                        evens.swap_remove(i);
-                    } else if evens[i] % 2 != 0 {
-                        // height becomes odd, move it to odds
-                        new_odds.push(evens.swap_remove(i));
+                bits
+            };
+            (bits != 0).then(|| (word * 64 + u64::from(bits.trailing_zeros())) * modulus + residue)
+        })
+    }
+}
+
+impl Tallies {
+    fn update(&mut self, group: u64, from: u64, to: u64) {
+        let residue = group % self.modulus;
+        let tally = self.classes.entry(residue).or_default();
+        tally.plants = tally.plants + to - from;
+        let j = group / self.modulus;
+        if from == 0 {
+            *tally.groups.entry(j / 64).or_default() |= 1 << (j % 64);
+        } else if to == 0 {
+            let bits = tally.groups.get_mut(&(j / 64)).unwrap();
+            *bits &= !(1 << (j % 64));
+            if *bits == 0 {
+                tally.groups.remove(&(j / 64));
+                if tally.groups.is_empty() {
+                    self.classes.remove(&residue);
+                }
+            }
+        }
+    }
+}
+
+// Part 1: a sprayed plant grows by 1, so it moves to the next residue class.
+//
+// The plants of a class grow together: they form a group, a tree whose nodes store the
+// growth of a plant relative to its parent. Spraying a class grows the root of each of its
+// groups, then merges it into the group of the next class, the smaller tree under the root
+// of the larger one. Spraying all the plants only shifts the numbering of the groups.
+
+/// Plants of one residue class under the rules of part 1.
+#[derive(Clone, Copy, Default)]
+struct Group {
+    root: Option<usize>,
+    plants: u64,
+}
+
+/// Merge of a sprayed group into the group of the next class.
+struct Merge {
+    index: u64,
+    sprayed: Group,
+    into: Group,
+}
+
+/// Change to the plants under the rules of part 1, with what it takes to cancel it.
+enum Growth {
+    Plant,
+    All,
+    Spray(Vec<Merge>),
+}
+
+/// Plants under the rules of part 1.
+struct Growing {
+    /// heights when planted, minus `all` at that time, wrapping
+    planted: Vec<u64>,
+    parent: Vec<usize>,
+    /// growth of each plant minus that of its parent, wrapping, or its own for a root
+    growth: Vec<u64>,
+    /// sprays of all the plants, whose growth is not stored in the groups
+    all: u64,
+    /// modulus of the residues that the groups are numbered by
+    modulus: u64,
+    /// group `i` holds the heights equal to `i + all` modulo `modulus`, if not empty
+    groups: HashMap<u64, Group>,
+    census: Census,
+    plants: u64,
+    sum: u64,
+    journal: Journal<Growth>,
+}
+
+impl Growing {
+    fn new(moduli: &[u64]) -> Self {
+        Self {
+            planted: Vec::new(),
+            parent: Vec::new(),
+            growth: Vec::new(),
+            all: 0,
+            modulus: moduli
+                .iter()
+                .fold(1, |common, &modulus| lcm(common, modulus).unwrap()),
+            groups: HashMap::new(),
+            census: Census::new(moduli),
+            plants: 0,
+            sum: 0,
+            journal: Journal::new(),
+        }
+    }
+
+    /// Number of the group of the heights with this residue, modulo a divisor of that of
+    /// the groups.
+    fn group(&self, residue: u64, modulus: u64) -> u64 {
+        let shift = self.all % modulus;
+        if residue >= shift {
+            residue - shift
+        } else {
+            residue + (modulus - shift)
+        }
+    }
+
+    fn plant(&mut self, height: u64) {
+        let plant = self.planted.len();
+        self.planted.push(height.wrapping_sub(self.all));
+        self.parent.push(plant);
+        self.growth.push(0);
+        self.plants += 1;
+        self.sum += height;
+
+        let index = self.group(height % self.modulus, self.modulus);
+        let group = self.groups.entry(index).or_default();
+        group.plants += 1;
+        self.census.update(index, group.plants - 1, group.plants);
+        match group.root {
+            Some(root) => {
+                self.parent[plant] = root;
+                self.growth[plant] = self.growth[root].wrapping_neg();
+            }
+            None => group.root = Some(plant),
+        }
+        self.journal.push(Growth::Plant);
+    }
+
+    fn spray(&mut self, target: Target) {
+        let (modulus, residue) = target.class();
+        if modulus == 1 {
+            self.all += 1;
+            self.sum += self.plants;
+            self.journal.push(Growth::All);
+            return;
+        }
+
+        let groups = &self.groups;
+        self.census.catch_up(modulus, || {
+            groups.iter().map(|(&index, group)| (index, group.plants))
+        });
+        let class = self.group(residue, modulus);
+        let journaled = self.journal.journals_next();
+        let mut merges = Vec::new();
+        // the census lags behind the groups until its next catch-up, so it still lists
+        // those of the class that have been merged
+        let mut after = None;
+        while let Some(index) = self.census.next_group(modulus, class, after) {
+            after = Some(index);
+            let group = self.groups.remove(&index).unwrap();
+            self.census.update(index, group.plants, 0);
+            if let Some(root) = group.root {
+                self.growth[root] = self.growth[root].wrapping_add(1);
+            }
+            self.sum += group.plants;
+            let next = (index + 1) % self.modulus;
+            let into = self.groups.remove(&next).unwrap_or_default();
+            let merged = self.merge(into, group);
+            self.groups.insert(next, merged);
+            self.census.update(next, into.plants, merged.plants);
+            if journaled {
+                merges.push(Merge {
+                    index,
+                    sprayed: group,
+                    into,
+                });
+            }
+        }
+        self.journal.push(Growth::Spray(merges));
+    }
+
+    fn merge(&mut self, a: Group, b: Group) -> Group {
+        let root = match (a.root, b.root) {
+            (Some(a_root), Some(b_root)) => {
+                let (root, other) = if a.plants >= b.plants {
+                    (a_root, b_root)
+                } else {
+                    (b_root, a_root)
+                };
+                self.parent[other] = root;
+                self.growth[other] = self.growth[other].wrapping_sub(self.growth[root]);
+                Some(root)
+            }
+            (root, other) => root.or(other),
+        };
+        Group {
+            root,
+            plants: a.plants + b.plants,
+        }
+    }
+
+    /// Number of changes in effect.
+    fn changes(&self) -> usize {
+        self.journal.len
+    }
+
+    /// Cancels the last change in effect.
+    fn undo(&mut self) {
+        match self.journal.pop() {
+            Some(Growth::Plant) => {
+                let height = self.planted.pop().unwrap().wrapping_add(self.all);
+                self.parent.pop();
+                self.growth.pop();
+                self.plants -= 1;
+                self.sum -= height;
+
+                let index = self.group(height % self.modulus, self.modulus);
+                let group = self.groups.get_mut(&index).unwrap();
+                group.plants -= 1;
+                self.census.update(index, group.plants + 1, group.plants);
+                if group.plants == 0 {
+                    self.groups.remove(&index);
+                }
+            }
+            Some(Growth::All) => {
+                self.all -= 1;
+                self.sum -= self.plants;
+            }
+            Some(Growth::Spray(merges)) => {
+                // the sprayed groups are restored once their classes have been emptied
+                for merge in merges.iter().rev() {
+                    let next = (merge.index + 1) % self.modulus;
+                    if let Some(root) = self.groups[&next].root {
+                        let other = if merge.sprayed.root == Some(root) {
+                            merge.into.root
+                        } else {
+                            merge.sprayed.root
+                        };
+                        if let Some(other) = other {
+                            self.parent[other] = other;
+                            self.growth[other] = self.growth[other].wrapping_add(self.growth[root]);
+                        }
+                    }
+                    if let Some(root) = merge.sprayed.root {
+                        self.growth[root] = self.growth[root].wrapping_sub(1);
+                    }
+                    self.sum -= merge.sprayed.plants;
+                    let merged = merge.into.plants + merge.sprayed.plants;
+                    self.census.update(next, merged, merge.into.plants);
+                    if merge.into.plants == 0 {
+                        self.groups.remove(&next);
                     } else {
-                        // height remains even, keep it
-                        i += 1;
+                        self.groups.insert(next, merge.into);
                     }
                 }
+                for merge in merges {
+                    self.groups.insert(merge.index, merge.sprayed);
+                    self.census.update(merge.index, 0, merge.sprayed.plants);
+                }
             }
+            None => {}
+        }
+    }
 
-            if b == "all" || b == "odd" {
-                let mut i = 0;
-                while i < odds.len() {
-                    odds[i] /= 2;
-                    if odds[i] == 0 {
-                        odds.swap_remove(i);
-                    } else if odds[i] % 2 == 0 {
-                        new_evens.push(odds.swap_remove(i));
-                    } else {
-                        i += 1;
+    fn height(&self, mut plant: usize) -> u64 {
+        let mut height = self.planted[plant].wrapping_add(self.all);
+        loop {
+            height = height.wrapping_add(self.growth[plant]);
+            if self.parent[plant] == plant {
+                return height;
+            }
+            plant = self.parent[plant];
+        }
+    }
+
+    fn count(&mut self, target: Target) -> u64 {
+        match target.class() {
+            (1, _) => self.plants,
+            (modulus, residue) => {
+                let groups = &self.groups;
+                self.census.catch_up(modulus, || {
+                    groups.iter().map(|(&index, group)| (index, group.plants))
+                });
+                self.census.plants(modulus, self.group(residue, modulus))
+            }
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.sum
+    }
+}
+
+// Part 2: a sprayed plant is halved, and removed once its height is 0.
+//
+// The plants are kept by residue class of their heights, modulo an even multiple of the
+// moduli of the targets, so that the heights of a class have the same parity: halving all
+// the plants of a class halves the sum of their heights exactly once the odd ones have lost
+// 1. A plant only needs to be touched when it changes class, which happens at most 64
+// times in its life as every halving shortens the binary writing of its height, unless
+// sprays are undone and done again.
+
+/// Plants of one residue class under the rules of part 2, halved lazily.
+#[derive(Default)]
+struct Class {
+    /// number of sprays received
+    sprays: usize,
+    plants: u64,
+    sum: u64,
+    /// plants leaving the class at each of its next sprays
+    leaving: VecDeque<Vec<usize>>,
+}
+
+/// Stay of a plant in the class of its height.
+#[derive(Clone, Copy, Default)]
+struct Stay {
+    class: u64,
+    /// height when it entered the class, 0 once the plant is removed
+    height: u64,
+    /// sprays of the class when it entered and when it leaves
+    since: usize,
+    until: usize,
+}
+
+/// Height after some halvings.
+fn halved(height: u64, halvings: usize) -> u64 {
+    height.checked_shr(halvings as u32).unwrap_or(0)
+}
+
+/// Spray of a class, with what it takes to cancel it.
+struct Sprayed {
+    class: u64,
+    sprays: usize,
+    sum: u64,
+    /// plants that left the class, with their stays in it
+    left: Vec<(usize, Stay)>,
+}
+
+/// Change to the plants under the rules of part 2, with what it takes to cancel it.
+enum Halved {
+    Plant,
+    Spray(Vec<Sprayed>),
+}
+
+/// Plants under the rules of part 2.
+struct Halving {
+    /// modulus of the residues that the classes are numbered by, even
+    modulus: u64,
+    stays: Vec<Stay>,
+    /// classes that are not empty
+    classes: HashMap<u64, Class>,
+    census: Census,
+    sum: u64,
+    journal: Journal<Halved>,
+}
+
+impl Halving {
+    fn new(moduli: &[u64]) -> Self {
+        Self {
+            modulus: moduli
+                .iter()
+                .fold(2, |common, &modulus| lcm(common, modulus).unwrap()),
+            stays: Vec::new(),
+            classes: HashMap::new(),
+            census: Census::new(moduli),
+            sum: 0,
+            journal: Journal::new(),
+        }
+    }
+
+    /// Adds a plant to the class of its height, unless it is 0.
+    fn insert(&mut self, plant: usize, height: u64) {
+        if height == 0 {
+            self.stays[plant] = Stay::default();
+            return;
+        }
+        let index = height % self.modulus;
+        let class = self.classes.entry(index).or_default();
+        let mut until = class.sprays + 1;
+        let mut next = height / 2;
+        while next > 0 && next % self.modulus == index {
+            next /= 2;
+            until += 1;
+        }
+        let later = until - class.sprays - 1;
+        if class.leaving.len() <= later {
+            class.leaving.resize_with(later + 1, Vec::new);
+        }
+        class.leaving[later].push(plant);
+        class.plants += 1;
+        class.sum += height;
+        self.sum += height;
+        self.census.update(index, class.plants - 1, class.plants);
+        self.stays[plant] = Stay {
+            class: index,
+            height,
+            since: class.sprays,
+            until,
+        };
+    }
+
+    /// Removes a plant from its class, the last one added to it.
+    fn remove(&mut self, plant: usize) {
+        let stay = self.stays[plant];
+        if stay.height == 0 {
+            return;
+        }
+        let class = self.classes.get_mut(&stay.class).unwrap();
+        class.leaving[stay.until - stay.since - 1].pop();
+        class.plants -= 1;
+        class.sum -= stay.height;
+        self.sum -= stay.height;
+        self.census
+            .update(stay.class, class.plants + 1, class.plants);
+        if class.plants == 0 {
+            self.classes.remove(&stay.class);
+        }
+    }
+
+    fn plant(&mut self, height: u64) {
+        self.stays.push(Stay::default());
+        self.insert(self.stays.len() - 1, height);
+        self.journal.push(Halved::Plant);
+    }
+
+    fn spray(&mut self, target: Target) {
+        let (modulus, residue) = target.class();
+        let classes = &self.classes;
+        self.census.catch_up(modulus, || {
+            classes.iter().map(|(&index, class)| (index, class.plants))
+        });
+        let mut sprayed = Vec::new();
+        let mut after = None;
+        while let Some(index) = self.census.next_group(modulus, residue, after) {
+            after = Some(index);
+            let class = self.classes.get_mut(&index).unwrap();
+            let (sprays, sum) = (class.sprays, class.sum);
+            class.sum = (class.sum - class.plants * (index % 2)) / 2;
+            class.sprays += 1;
+            let left = class.leaving.pop_front().unwrap_or_default();
+            class.plants -= left.len() as u64;
+            for &plant in &left {
+                let stay = self.stays[plant];
+                class.sum -= halved(stay.height, stay.until - stay.since);
+            }
+            self.sum -= sum - class.sum;
+            self.census
+                .update(index, class.plants + left.len() as u64, class.plants);
+            if class.plants == 0 {
+                self.classes.remove(&index);
+            }
+            sprayed.push(Sprayed {
+                class: index,
+                sprays,
+                sum,
+                left: left
+                    .into_iter()
+                    .map(|plant| (plant, self.stays[plant]))
+                    .collect(),
+            });
+        }
+        // the plants that left a class join the next ones once they have all been sprayed
+        for Sprayed { left, .. } in &sprayed {
+            for &(plant, stay) in left {
+                self.insert(plant, halved(stay.height, stay.until - stay.since));
+            }
+        }
+        self.journal.push(Halved::Spray(sprayed));
+    }
+
+    /// Cancels the last change in effect.
+    fn undo(&mut self) {
+        match self.journal.pop() {
+            Some(Halved::Plant) => {
+                self.remove(self.stays.len() - 1);
+                self.stays.pop();
+            }
+            Some(Halved::Spray(sprayed)) => {
+                // the plants joined their new classes in this order
+                for Sprayed { left, .. } in sprayed.iter().rev() {
+                    for &(plant, _) in left.iter().rev() {
+                        self.remove(plant);
+                    }
+                }
+                for Sprayed {
+                    class: index,
+                    sprays,
+                    sum,
+                    left,
+                } in sprayed
+                {
+                    let class = self.classes.entry(index).or_default();
+                    self.sum += sum - class.sum;
+                    class.sum = sum;
+                    class.sprays = sprays;
+                    class.plants += left.len() as u64;
+                    self.census
+                        .update(index, class.plants - left.len() as u64, class.plants);
+                    let mut plants = Vec::with_capacity(left.len());
+                    for (plant, stay) in left {
+                        self.stays[plant] = stay;
+                        plants.push(plant);
                     }
+                    class.leaving.push_front(plants);
                 }
             }
+            None => {}
+        }
+    }
 
-            evens.extend(new_evens);
-            odds.extend(new_odds);
+    fn height(&self, plant: usize) -> u64 {
+        let stay = self.stays[plant];
+        if stay.height == 0 {
+            return 0;
         }
+        halved(stay.height, self.classes[&stay.class].sprays - stay.since)
     }
 
-    evens.iter().sum::<u64>() + odds.iter().sum::<u64>()
+    fn count(&mut self, target: Target) -> u64 {
+        let (modulus, residue) = target.class();
+        let classes = &self.classes;
+        self.census.catch_up(modulus, || {
+            classes.iter().map(|(&index, class)| (index, class.plants))
+        });
+        self.census.plants(modulus, residue)
+    }
+
+    fn total(&self) -> u64 {
+        self.sum
+    }
 }
 
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
+plant 5
+spray even
+spray odd
+plant 9
+spray all
+plant 4
+spray even
+";
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::property::{self, Source};
+    use crate::rng::Rng;
+
+    fn part1(commands: &[Command]) -> u64 {
+        replay(commands).0
+    }
+
+    fn is_targeted(target: Target, height: u64) -> bool {
+        match target {
+            Target::All => true,
+            Target::Residue { residue, modulus } => height % modulus == residue,
+        }
+    }
+
+    /// Part 2 alone, whose heights may not fit under the rules of part 1.
+    fn part2(commands: &[Command]) -> u64 {
+        let mut plants = Halving::new(&moduli(commands));
+        for &command in commands {
+            match command {
+                Command::Plant(height) => plants.plant(height),
+                Command::Spray(target) => plants.spray(target),
+                _ => {}
+            }
+        }
+        plants.total()
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 23);
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 5);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input("plant 1\nprune 2"),
+            Err("line 2, column 1: unknown command \"prune\"".to_string())
+        );
+        assert_eq!(
+            parse_input("spray some"),
+            Err("line 1, column 6: unknown target \"some\"".to_string())
+        );
+        assert_eq!(
+            parse_input("plant"),
+            Err("line 1, column 6: expected a number, found end of line".to_string())
+        );
+        assert_eq!(
+            parse_input("spray"),
+            Err("line 1, column 6: expected a word, found end of line".to_string())
+        );
+        assert!(parse_input("plant -1").is_err());
+        assert!(parse_input("plant 1 2").is_err());
+        assert!(parse_input("plant1").is_err());
+        assert_eq!(
+            parse_input("plant 18446744073709551616"),
+            Err("line 1, column 7: invalid number 18446744073709551616: \
+                 number too large to fit in target type"
+                .to_string())
+        );
+        assert_eq!(
+            parse_input("plant 18446744073709551615\nspray all"),
+            Err(
+                "line 2, column 1: the heights may add up to more than 18446744073709551615"
+                    .to_string()
+            )
+        );
+        assert!(parse_input("plant 9223372036854775808\nplant 9223372036854775808").is_err());
+        assert_eq!(
+            parse_input("plant 1\nquery 2"),
+            Err("line 2, column 6: no plant #2 yet".to_string())
+        );
+        assert!(parse_input("query 0").is_err());
+        assert!(parse_input("count some").is_err());
+        assert!(parse_input("total 1").is_err());
+        assert_eq!(
+            parse_input("spray 3 mod 3"),
+            Err("line 1, column 6: residue 3 not below the modulus 3".to_string())
+        );
+        assert!(parse_input("spray 0 mod 0").is_err());
+        assert_eq!(
+            parse_input("count 18446744073709551616 mod 2"),
+            Err("line 1, column 6: residue 18446744073709551616 out of range".to_string())
+        );
+        assert!(parse_input("spray 1 3").is_err());
+        assert!(parse_input("count 1 mod").is_err());
+        assert_eq!(
+            parse_input("checkpoint a\nundo"),
+            Err("line 2, column 1: nothing to undo".to_string())
+        );
+        assert!(parse_input("plant 1\nrollback a").is_err());
+        assert_eq!(
+            parse_input("plant 1\ncheckpoint a\nundo\nplant 1\nrollback a"),
+            Err("line 5, column 9: no checkpoint \"a\"".to_string())
+        );
+        // the sprays that are cancelled no longer count
+        let tall = format!("plant {}\ncheckpoint a\n", u64::MAX - 1);
+        let sprays = "spray all\nundo\n".repeat(10);
+        assert!(parse_input(&format!("{tall}{sprays}")).is_ok());
+        let rollbacks = "spray all\nrollback a\n".repeat(10);
+        assert!(parse_input(&format!("{tall}{rollbacks}")).is_ok());
+        assert!(parse_input(&format!("{tall}{sprays}spray all\nspray all")).is_err());
+        assert_eq!(
+            parse_input("plant 1\nundo\nquery 1"),
+            Err("line 3, column 6: no plant #1 yet".to_string())
+        );
+        assert!(parse_input("spray 1 mod 1000\ncount 0 mod 1024\ncount 0 mod 1021").is_ok());
+        assert_eq!(
+            parse_input("spray 1 mod 4294967296\ncount 0 mod 4294967295\ncount 0 mod 4294967291"),
+            Err(format!(
+                "line 3, column 1: the moduli of the targets have no common multiple up to {}",
+                u64::MAX
+            ))
+        );
+    }
 
-    const TEST_INPUT: &str = "\
+    #[test]
+    fn test_residue_sprays() {
+        let input = "plant 4\nplant 5\nspray 1 mod 3\ntotal\ncount 2 mod 3\nspray even";
+        let (part1, part2, answers) = replay(&parse_input(input).unwrap());
+        assert_eq!((part1, part2), (10, 6));
+        assert_eq!(
+            answers,
+            [
+                "total: 10 (part 1), 7 (part 2)",
+                "2 mod 3 plants: 2 (part 1), 2 (part 2)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = "\
 plant 5
+plant 6
 spray even
+query 1
+query 2
+count odd
 spray odd
-plant 9
+count even
 spray all
+total
+plant 0
+query 3
+";
+        let (_, _, answers) = replay(&parse_input(input).unwrap());
+        assert_eq!(
+            answers,
+            [
+                "plant #1: 5 (part 1), 5 (part 2)",
+                "plant #2: 7 (part 1), 3 (part 2)",
+                "odd plants: 2 (part 1), 2 (part 2)",
+                "even plants: 2 (part 1), 1 (part 2)",
+                "total: 16 (part 1), 1 (part 2)",
+                "plant #3: 0 (part 1), 0 (part 2)",
+            ]
+        );
+    }
+
+    /// Heights of every plant under both rules, plant by plant.
+    fn heights_naive(commands: &[Command]) -> Vec<(u64, u64)> {
+        // states after each change in effect
+        let mut states: Vec<Vec<(u64, u64)>> = vec![Vec::new()];
+        for &command in commands {
+            let mut heights = states.last().unwrap().clone();
+            match command {
+                Command::Plant(height) => heights.push((height, height)),
+                Command::Spray(target) => {
+                    let sprayed = |height| is_targeted(target, height);
+                    for (grown, halved) in &mut heights {
+                        if sprayed(*grown) {
+                            *grown += 1;
+                        }
+                        if *halved > 0 && sprayed(*halved) {
+                            *halved /= 2;
+                        }
+                    }
+                }
+                Command::Undo => {
+                    states.pop();
+                    continue;
+                }
+                Command::Rollback(kept) => {
+                    states.truncate(kept + 1);
+                    continue;
+                }
+                _ => continue,
+            }
+            states.push(heights);
+        }
+        states.pop().unwrap()
+    }
+
+    #[test]
+    fn test_undo_and_rollback() {
+        let input = "\
+plant 5
+checkpoint start
+spray odd
 plant 4
+undo
+query 1
+rollback start
 spray even
+query 1
+checkpoint start
+plant 2
+rollback start
+total
 ";
+        let (part1, part2, answers) = replay(&parse_input(input).unwrap());
+        assert_eq!((part1, part2), (5, 5));
+        assert_eq!(
+            answers,
+            [
+                "plant #1: 6 (part 1), 2 (part 2)",
+                "plant #1: 5 (part 1), 5 (part 2)",
+                "total: 5 (part 1), 5 (part 2)",
+            ]
+        );
+    }
 
     #[test]
-    fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 23);
+    fn test_settled() {
+        let input = "\
+plant 1
+plant 2
+checkpoint a
+spray all
+undo
+plant 3
+total
+rollback a
+";
+        assert_eq!(settled(&parse_input(input).unwrap()), [1, 2, 2, 2, 2, 2, 2]);
+        assert_eq!(
+            settled(&parse_input(EXAMPLE).unwrap()),
+            [1, 2, 3, 4, 5, 6, 7]
+        );
+
+        let mut journal = Journal::new();
+        journal.settle(1);
+        journal.push('a');
+        journal.push('b');
+        journal.push('c');
+        assert_eq!(journal.entries, ['b', 'c']);
+        journal.settle(2);
+        assert_eq!(journal.entries, ['c']);
+        assert_eq!(journal.pop(), Some('c'));
+        assert_eq!(journal.len, 2);
     }
 
     #[test]
-    fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 5);
+    fn test_undo_matches_simulation() {
+        let mut rng = Rng::new(46);
+        let lines = [
+            "plant 7",
+            "plant 12",
+            "plant 1",
+            "spray all",
+            "spray even",
+            "spray odd",
+            "spray 1 mod 3",
+            "spray 0 mod 4",
+            "undo",
+            "checkpoint a",
+            "checkpoint b",
+            "rollback a",
+            "rollback b",
+        ];
+        for _ in 0..100 {
+            // lines that would make the log invalid are left out
+            let mut log = String::new();
+            for _ in 0..rng.below(50) {
+                let line = lines[rng.below(lines.len())];
+                if parse_input(&format!("{log}{line}\n")).is_ok() {
+                    log = format!("{log}{line}\n");
+                }
+            }
+            let mut commands = parse_input(&log).unwrap();
+            let heights = heights_naive(&commands);
+            commands.extend((1..=heights.len()).map(Command::Query));
+
+            let (part1, part2, answers) = replay(&commands);
+            let grown = heights.iter().map(|h| h.0).sum();
+            let halved = heights.iter().map(|h| h.1).sum();
+            assert_eq!((part1, part2), (grown, halved), "{log}");
+            let expected: Vec<String> = heights
+                .iter()
+                .enumerate()
+                .map(|(i, (grown, halved))| {
+                    format!("plant #{}: {grown} (part 1), {halved} (part 2)", i + 1)
+                })
+                .collect();
+            assert_eq!(answers, expected, "{log}");
+        }
+    }
+
+    #[test]
+    fn test_heights_match_simulation() {
+        let mut rng = Rng::new(44);
+        for _ in 0..100 {
+            let mut target = || match rng.below(4) {
+                0 => Target::All,
+                _ => {
+                    let modulus = 1 + rng.below(6) as u64;
+                    Target::Residue {
+                        residue: rng.next() % modulus,
+                        modulus,
+                    }
+                }
+            };
+            let targets: Vec<Target> = (0..3).map(|_| target()).collect();
+            let mut commands: Vec<Command> = (0..rng.below(60))
+                .map(|_| match rng.below(3) {
+                    0 => Command::Spray(targets[rng.below(3)]),
+                    _ => Command::Plant(rng.next() >> (40 + rng.below(24))),
+                })
+                .collect();
+            let heights = heights_naive(&commands);
+            commands.extend((1..=heights.len()).map(Command::Query));
+            commands.extend(targets.iter().map(|&target| Command::Count(target)));
+
+            let (_, _, answers) = replay(&commands);
+            let mut expected: Vec<String> = heights
+                .iter()
+                .enumerate()
+                .map(|(i, (grown, halved))| {
+                    format!("plant #{}: {grown} (part 1), {halved} (part 2)", i + 1)
+                })
+                .collect();
+            for &target in &targets {
+                let grown = heights.iter().filter(|h| is_targeted(target, h.0)).count();
+                let halved = heights
+                    .iter()
+                    .filter(|h| h.1 > 0 && is_targeted(target, h.1))
+                    .count();
+                expected.push(format!(
+                    "{target} plants: {grown} (part 1), {halved} (part 2)"
+                ));
+            }
+            assert_eq!(answers, expected);
+        }
+    }
+
+    #[test]
+    fn test_large_moduli() {
+        let mut rng = Rng::new(45);
+        let moduli = [1000, 1021, 1 << 40];
+        // enough plants for the tallies to be counted again when the sprays come
+        let mut commands: Vec<Command> = (0..3000)
+            .map(|_| Command::Plant(rng.below(50) as u64))
+            .collect();
+        let mut targets = Vec::new();
+        for _ in 0..40 {
+            let target = Target::Residue {
+                residue: rng.below(60) as u64,
+                modulus: moduli[rng.below(3)],
+            };
+            commands.push(Command::Spray(target));
+            targets.push(target);
+        }
+        let heights = heights_naive(&commands);
+        commands.extend(targets.iter().map(|&target| Command::Count(target)));
+
+        let (_, _, answers) = replay(&commands);
+        let expected: Vec<String> = targets
+            .iter()
+            .map(|&target| {
+                let grown = heights.iter().filter(|h| is_targeted(target, h.0)).count();
+                let halved = heights
+                    .iter()
+                    .filter(|h| h.1 > 0 && is_targeted(target, h.1))
+                    .count();
+                format!("{target} plants: {grown} (part 1), {halved} (part 2)")
+            })
+            .collect();
+        assert_eq!(answers, expected);
+    }
+
+    /// Part 2 plant by plant: every spray walks the sprayed plants.
+    fn part2_naive(commands: &[Command]) -> u64 {
+        let mut heights: Vec<u64> = Vec::new();
+        for &command in commands {
+            match command {
+                Command::Plant(height) => heights.push(height),
+                Command::Spray(target) => {
+                    for height in &mut heights {
+                        if is_targeted(target, *height) {
+                            *height /= 2;
+                        }
+                    }
+                    heights.retain(|&h| h > 0);
+                }
+                _ => {}
+            }
+        }
+        heights.iter().sum()
+    }
+
+    #[test]
+    fn test_part2_large_heights() {
+        let commands = [
+            Command::Plant(u64::MAX),
+            Command::Spray(Target::ODD),
+            Command::Plant(1 << 63),
+            Command::Spray(Target::All),
+            Command::Plant(u64::MAX / 3),
+            Command::Spray(Target::EVEN),
+        ];
+        assert_eq!(part2(&commands), part2_naive(&commands));
+        let sprays = [Command::Spray(Target::All); 64];
+        assert_eq!(part2(&[&commands[..], &sprays].concat()), 0);
+    }
+
+    #[test]
+    fn test_part2_long_log() {
+        let mut rng = Rng::new(13);
+        let targets = [Target::All, Target::EVEN, Target::ODD];
+        let commands: Vec<Command> = (0..200_000)
+            .map(|_| match rng.below(4) {
+                0 => Command::Spray(targets[rng.below(3)]),
+                _ => Command::Plant(rng.next() >> (24 + rng.below(40))),
+            })
+            .collect();
+        assert_eq!(part2(&commands), part2_naive(&commands));
+    }
+
+    /// Plantation log of a few plants and sprays.
+    fn commands(s: &mut Source) -> Vec<String> {
+        s.vec(0..=30, |s| {
+            if s.bool() {
+                format!("spray {}", s.choose(&["all", "even", "odd"]))
+            } else {
+                format!("plant {}", s.int(0u64..=1000))
+            }
+        })
+    }
+
+    /// Applies the log plant by plant, `spray` giving the new height of a sprayed plant.
+    fn simulate(commands: &[String], spray: fn(u64) -> u64) -> u64 {
+        let mut heights: Vec<u64> = Vec::new();
+        for command in commands {
+            match command.split_once(' ').unwrap() {
+                ("plant", height) => heights.push(height.parse().unwrap()),
+                (_, target) => {
+                    for height in heights.iter_mut() {
+                        if target == "all" || (target == "even") == height.is_multiple_of(2) {
+                            *height = spray(*height);
+                        }
+                    }
+                }
+            }
+        }
+        heights.iter().sum()
+    }
+
+    #[test]
+    fn test_part1_matches_simulation() {
+        property::check(commands, |commands| {
+            part1(&parse_input(&commands.join("\n")).unwrap()) == simulate(commands, |h| h + 1)
+        });
+    }
+
+    #[test]
+    fn test_part2_matches_simulation() {
+        property::check(commands, |commands| {
+            part2(&parse_input(&commands.join("\n")).unwrap()) == simulate(commands, |h| h / 2)
+        });
     }
 }
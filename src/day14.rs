@@ -1,24 +1,104 @@
 // Day 14: Flip-Flop
 // https://lovemathboy.github.io/day14.html
 
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day14.txt").ok()?;
+use crate::bigint::BigUint;
+use crate::graph::{self, Graph};
+use crate::inspect::Report;
+use crate::modint::Modulus;
+use crate::parse;
+use crate::render::Picture;
+use crate::trace;
 
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    Ok((part1(input)?.to_string(), part2(input)?.to_string()))
 }
 
-fn part1(input: &str) -> u32 {
-    let mut circuit = Circuit::from(input);
-    circuit.run(123456)
+fn part1(input: &str) -> Result<BigUint, String> {
+    let circuit = Circuit::parse(input)?;
+    if let Some(count) = circuit.run_within(123456, MAX_RUN) {
+        return Ok(BigUint::from(u64::from(count)));
+    }
+    trace::info!("the pulses take more than {MAX_RUN} moves: counting them exactly");
+    let mut pulses = circuit.exact_pulses(&BigUint::from(123456u64))?;
+    Ok(mem::take(&mut pulses[SIGNAL_OUT]))
 }
 
-fn part2(input: &str) -> u128 {
-    let circuit = Circuit::from(input);
+fn part2(input: &str) -> Result<u128, String> {
+    let circuit = Circuit::parse(input)?;
     circuit.solve(12, 3456, 10u128.pow(15))
 }
 
+/// Pulses reaching OUT in part 2, before they are reduced by the modulus.
+pub fn exact(input: &str) -> Result<BigUint, String> {
+    let circuit = Circuit::parse(input)?;
+    let mut pulses = circuit.exact_pulses(&BigUint::from(12u64).pow(3456))?;
+    Ok(mem::take(&mut pulses[SIGNAL_OUT]))
+}
+
+/// Graph of the circuit, annotated with the pulse counts of part 2 when they can be counted.
+pub fn render(input: &str) -> Result<Vec<Picture>, String> {
+    let circuit = Circuit::parse(input)?;
+    let pulses = match circuit.pulses(12, 3456, 10u128.pow(15)) {
+        Ok(pulses) => Some(pulses),
+        Err(e) => {
+            trace::warning!("part 2 cannot count the pulses: {e}");
+            None
+        }
+    };
+    Ok(vec![Picture::Dot(circuit.dot(pulses.as_deref()))])
+}
+
+/// Size of the circuit, and whether part 2 can count the pulses reaching OUT.
+pub fn inspect(input: &str, report: &mut Report) -> Result<(), String> {
+    let circuit = Circuit::parse(input)?;
+    report.stat("flip-flops", circuit.circuit.len() - 3);
+    report.stat("reachable from INP", circuit.reachable().len());
+
+    match circuit.topo_from_inp() {
+        Ok(order) => {
+            let depth = circuit.compute_depths_to_out(&order);
+            report.stat(
+                "longest path to OUT",
+                depth.values().copied().max().unwrap_or(0),
+            );
+        }
+        Err(e) => report.stat("longest path to OUT", format!("unbounded, {e}")),
+    }
+
+    let reaching_out = circuit
+        .reachable()
+        .into_iter()
+        .any(|node| circuit.circuit[node].outputs.contains(&SIGNAL_OUT));
+    if !reaching_out {
+        report.violation("OUT cannot be reached from INP: part 2 counts no pulse");
+    } else if let Err(e) = part2(input) {
+        report.violation(format!("part 2 cannot count the pulses: {e}"));
+    }
+    Ok(())
+}
+
+/// Moves of the pulses one by one after which part 1 counts them exactly instead.
+const MAX_RUN: usize = 1 << 24;
+
+/// Moves of the pulses after which a cycle stops being settled move by move, and is
+/// settled through the pulses received by one of its flip-flops instead: leaving some
+/// cycles takes a pulse exponentially many moves in their length.
+const MAX_MOVES: usize = 1 << 16;
+
+/// Steps of the settling, over all the attempts of the pivots, after which the counts are
+/// given up: a cycle whose pivot leaves other large cycles may take exponentially many
+/// attempts in its size. A step moves the pulses out of a flip-flop, copies the pulses
+/// waiting at one, or computes an entry of a system of lower bounds.
+const MAX_SETTLING: usize = 1 << 22;
+
+/// Most flip-flops in a cycle for which part 2 bounds the pulse counts with a linear
+/// system, solved with machine integers.
+const MAX_SYSTEM: usize = 40;
+
 type SignalId = usize;
 
 const SIGNAL_INP: SignalId = 0;
@@ -31,6 +111,7 @@ struct FlipFlop {
     outputs: [SignalId; 2],
 }
 
+#[cfg(test)]
 impl FlipFlop {
     fn run(&mut self) -> SignalId {
         let next = self.outputs[usize::from(self.state)];
@@ -53,7 +134,26 @@ impl<'a> Mapping<'a> {
     }
 
     fn size(&self) -> usize {
-        self.next_id + 3
+        self.next_id
+    }
+
+    fn name(&self, id: SignalId) -> &'a str {
+        self.map
+            .iter()
+            .find(|&(_, &v)| v == id)
+            .map_or("?", |(&name, _)| name)
+    }
+
+    /// Name of every signal, by ID.
+    fn names(&self) -> Vec<String> {
+        let mut names = vec![String::new(); self.next_id];
+        names[SIGNAL_INP] = "INP".to_string();
+        names[SIGNAL_OUT] = "OUT".to_string();
+        names[SIGNAL_BIN] = "BIN".to_string();
+        for (&name, &id) in &self.map {
+            names[id] = name.to_string();
+        }
+        names
     }
 
     fn signal_id(&mut self, name: &'a str) -> SignalId {
@@ -78,29 +178,46 @@ impl<'a> Mapping<'a> {
 struct Circuit {
     circuit: Vec<FlipFlop>,
     start: SignalId,
+    /// by signal ID
+    names: Vec<String>,
 }
 
 impl Circuit {
-    fn from(input: &str) -> Self {
+    fn parse(input: &str) -> Result<Self, String> {
         let mut signals = Mapping::new();
 
         let mut circuit = Vec::new();
-        let mut start = 0;
+        let mut defined = Vec::new();
+        let mut start = None;
 
-        for line in input.lines() {
-            let (src, b) = line.split_once(':').unwrap();
+        for mut line in parse::lines(input) {
+            let at_src = line;
+            let src = line.until(":")?;
+            if src.is_empty() || src.contains(char::is_whitespace) {
+                return Err(at_src.error("expected '<signal>: <outputs>'"));
+            }
 
             let src_id = signals.signal_id(src);
 
             if src_id == SIGNAL_INP {
-                let wire = b.trim_ascii();
-                start = signals.signal_id(wire);
+                let wire = line.word()?;
+                line.end()?;
+                start = Some(signals.signal_id(wire));
+            } else if src_id == SIGNAL_OUT || src_id == SIGNAL_BIN {
+                return Err(at_src.error(format!("{src} cannot have outputs")));
             } else {
-                let (flip, flop) = b.trim_ascii().split_once(' ').unwrap();
+                let at_outputs = line;
+                let flip = line.word()?;
+                let flop = line.word()?;
+                line.end()?;
 
                 let flip_id = signals.signal_id(flip);
                 let flop_id = signals.signal_id(flop);
 
+                if flip_id == SIGNAL_INP || flop_id == SIGNAL_INP {
+                    return Err(at_outputs.error("INP cannot be an output"));
+                }
+
                 circuit.resize(
                     signals.size(),
                     FlipFlop {
@@ -108,15 +225,114 @@ impl Circuit {
                         outputs: [0, 0],
                     },
                 );
+                defined.resize(signals.size(), false);
 
                 circuit[src_id].outputs[0] = flip_id;
                 circuit[src_id].outputs[1] = flop_id;
+                defined[src_id] = true;
             }
         }
 
-        Self { circuit, start }
+        let Some(start) = start else {
+            return Err("missing INP".to_string());
+        };
+        if start < 3 {
+            return Err("INP must be wired to a flip-flop".to_string());
+        }
+
+        circuit.resize(
+            signals.size(),
+            FlipFlop {
+                state: false,
+                outputs: [0, 0],
+            },
+        );
+        defined.resize(signals.size(), false);
+
+        if let Some(id) = (3..signals.size()).find(|&id| !defined[id]) {
+            return Err(format!("signal {} is never defined", signals.name(id)));
+        }
+
+        let circuit = Self {
+            circuit,
+            start,
+            names: signals.names(),
+        };
+
+        // every pulse must eventually reach OUT or BIN
+        let leaves = circuit.reaching_out_or_bin();
+        if let Some(id) = circuit.reachable().into_iter().find(|&id| !leaves[id]) {
+            return Err(format!(
+                "a pulse reaching {} never leaves the circuit",
+                signals.name(id)
+            ));
+        }
+
+        Ok(circuit)
     }
 
+    /// Flip-flops reachable from INP.
+    fn reachable(&self) -> Vec<SignalId> {
+        let mut seen = vec![false; self.circuit.len()];
+        let mut stack = vec![self.start];
+        let mut nodes = Vec::new();
+        seen[self.start] = true;
+
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            for &child in &self.circuit[node].outputs {
+                if child >= 3 && !seen[child] {
+                    seen[child] = true;
+                    stack.push(child);
+                }
+            }
+        }
+        nodes
+    }
+
+    /// For each signal, whether OUT or BIN can be reached from it.
+    fn reaching_out_or_bin(&self) -> Vec<bool> {
+        let mut leaves = vec![false; self.circuit.len()];
+        leaves[SIGNAL_OUT] = true;
+        leaves[SIGNAL_BIN] = true;
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for node in 3..self.circuit.len() {
+                if !leaves[node] && self.circuit[node].outputs.iter().any(|&o| leaves[o]) {
+                    leaves[node] = true;
+                    changed = true;
+                }
+            }
+        }
+        leaves
+    }
+
+    /// Pulses reaching OUT when `n` pulses enter the circuit, moved one by one, `None` if
+    /// they take more than `max_moves` moves.
+    fn run_within(&self, n: usize, max_moves: usize) -> Option<u32> {
+        let mut state: Vec<bool> = self.circuit.iter().map(|ff| ff.state).collect();
+        let mut moves = 0;
+        let mut count = 0;
+        for _ in 0..n {
+            let mut signal = self.start;
+            while signal != SIGNAL_OUT && signal != SIGNAL_BIN {
+                moves += 1;
+                if moves > max_moves {
+                    return None;
+                }
+                let next = self.circuit[signal].outputs[usize::from(state[signal])];
+                state[signal] = !state[signal];
+                signal = next;
+            }
+            count += u32::from(signal == SIGNAL_OUT);
+        }
+        Some(count)
+    }
+
+    /// Pulses reaching OUT when `n` pulses enter the circuit, moved one by one.
+    #[cfg(test)]
     fn run(&mut self, n: usize) -> u32 {
         let mut count = 0;
         for _ in 0..n {
@@ -137,40 +353,22 @@ impl Circuit {
         count
     }
 
-    fn topo_from_inp(&self) -> Vec<SignalId> {
-        let mut state = HashMap::new();
-        let mut order = Vec::new();
-        let mut stack = Vec::new();
-        stack.push((self.start, 0));
-
-        while let Some((node, phase)) = stack.pop() {
-            if state.get(&node) == Some(&2) {
-                continue;
-            }
-
-            if phase == 1 {
-                state.insert(node, 2);
-                order.push(node);
-                continue;
-            }
-
-            if state.get(&node) == Some(&1) {
-                continue;
-            }
-            state.insert(node, 1);
-            stack.push((node, 1));
-
-            for child in self.circuit[node].outputs.iter() {
-                if *child == SIGNAL_BIN || *child == SIGNAL_OUT {
-                    continue;
-                }
-                if state.get(child) != Some(&2) {
-                    stack.push((*child, 0));
+    /// Graph of the flip-flops, OUT and BIN being left out.
+    fn graph(&self) -> Graph {
+        let mut graph = Graph::new(self.circuit.len());
+        for node in 3..self.circuit.len() {
+            for &child in &self.circuit[node].outputs {
+                if child >= 3 {
+                    graph.add_edge(node, child);
                 }
             }
         }
-        order.reverse();
-        order
+        graph
+    }
+
+    fn topo_from_inp(&self) -> Result<Vec<SignalId>, String> {
+        graph::topological_order(&self.graph(), [self.start])
+            .map_err(|node| format!("the circuit has a cycle through {}", self.names[node]))
     }
 
     fn compute_depths_to_out(&self, order: &[SignalId]) -> HashMap<SignalId, usize> {
@@ -192,114 +390,653 @@ impl Circuit {
         depth
     }
 
-    fn solve(&self, base: u128, exp: u32, modulus: u128) -> u128 {
-        let order = self.topo_from_inp();
+    fn solve(&self, base: u128, exp: u32, modulus: u128) -> Result<u128, String> {
+        let pulses = self.pulses(base, exp, modulus)?;
+        Ok(pulses[SIGNAL_OUT].map_or(0, |(count, _)| count))
+    }
+
+    /// Pulses received by every signal when `base^exp` pulses enter the circuit, as a
+    /// count and the modulus that it is reduced by, which is `modulus` for OUT.
+    /// `None` for the signals whose count is not needed to count those of OUT.
+    fn pulses(
+        &self,
+        base: u128,
+        exp: u32,
+        modulus: u128,
+    ) -> Result<Vec<Option<(u128, u128)>>, String> {
+        let order = match self.topo_from_inp() {
+            Ok(order) => order,
+            Err(e) => {
+                trace::info!("{e}: counting the pulses exactly");
+                return self.exact_pulses_mod(base, exp, modulus);
+            }
+        };
 
         let depth = self.compute_depths_to_out(&order);
 
         let mut mod_map = HashMap::new();
         for (node, d) in depth.iter() {
             if *d > 0 {
-                let zz = modulus << d;
-                if zz >> d != modulus {
-                    panic!("overflow");
+                // keep one spare bit so that sums of residues cannot overflow
+                if *d >= 127 || (modulus << (d + 1)) >> (d + 1) != modulus {
+                    trace::info!(
+                        "circuit too deep for modulus {modulus}: counting the pulses exactly"
+                    );
+                    return self.exact_pulses_mod(base, exp, modulus);
                 }
                 mod_map.insert(*node, modulus << d);
             }
         }
 
+        let mut pulses = vec![None; self.circuit.len()];
+        let Some(&start_mod) = mod_map.get(&self.start) else {
+            // no path to OUT
+            return Ok(pulses);
+        };
+
         let mut counts = HashMap::new();
         let mut out_count = 0;
-
-        counts.insert(self.start, total_mod(base, exp, mod_map[&self.start]));
+        counts.insert(self.start, total_mod(base, exp, start_mod));
 
         for node in order {
-            let n_mod = counts[&node] % mod_map[&node];
+            let Some(&node_mod) = mod_map.get(&node) else {
+                continue;
+            };
+            let n_mod = counts[&node] % node_mod;
+            trace::info!("{}: {n_mod} pulses (mod {node_mod})", self.names[node]);
+            pulses[node] = Some((n_mod, node_mod));
             for (idx, child) in self.circuit[node].outputs.iter().enumerate() {
                 if *child == SIGNAL_OUT {
                     let send = split_mod(n_mod, modulus, idx == 0);
                     out_count = (out_count + send) % modulus;
-                } else if *child == SIGNAL_BIN {
-                    continue;
-                } else {
-                    let mod_child = mod_map[child];
+                } else if let Some(&mod_child) = mod_map.get(child) {
                     let send = split_mod(n_mod, mod_child, idx == 0);
                     counts.insert(*child, (counts.get(child).unwrap_or(&0) + send) % mod_child);
                 }
             }
         }
-        out_count
+        trace::info!("OUT: {out_count} pulses (mod {modulus})");
+        pulses[SIGNAL_OUT] = Some((out_count, modulus));
+        Ok(pulses)
+    }
+
+    /// `pulses` for a circuit with cycles or too deep for `modulus`, whose paths to OUT
+    /// are too long to reduce the counts along the way: the pulses are counted exactly,
+    /// then reduced by `modulus`.
+    fn exact_pulses_mod(
+        &self,
+        base: u128,
+        exp: u32,
+        modulus: u128,
+    ) -> Result<Vec<Option<(u128, u128)>>, String> {
+        let exact = self.exact_pulses(&BigUint::from(base).pow(exp))?;
+        let big_modulus = BigUint::from(modulus);
+
+        let mut pulses = vec![None; self.circuit.len()];
+        for node in self.reachable().into_iter().chain([SIGNAL_OUT]) {
+            let count = u128::try_from(&(&exact[node] % &big_modulus))?;
+            trace::info!("{}: {count} pulses (mod {modulus})", self.names[node]);
+            pulses[node] = Some((count, modulus));
+        }
+        Ok(pulses)
+    }
+
+    /// Number of pulses received by every signal when `pulses` enter the circuit.
+    ///
+    /// A flip-flop that receives k pulses sends half of them to each output, the one it
+    /// points to receiving the odd one out. The order in which the pulses move does not
+    /// change the counts, so the pulses waiting at a flip-flop move together, the strongly
+    /// connected components of the circuit being settled from INP onwards. Fails if settling
+    /// takes more than `MAX_SETTLING` steps.
+    fn exact_pulses(&self, pulses: &BigUint) -> Result<Vec<BigUint>, String> {
+        self.exact_pulses_within(pulses, MAX_MOVES)
+    }
+
+    /// `exact_pulses`, the cycles being settled move by move for at most `max_moves`.
+    fn exact_pulses_within(
+        &self,
+        pulses: &BigUint,
+        max_moves: usize,
+    ) -> Result<Vec<BigUint>, String> {
+        let mut flow = Flow {
+            state: self.circuit.iter().map(|ff| ff.state).collect(),
+            waiting: vec![BigUint::default(); self.circuit.len()],
+            received: vec![BigUint::default(); self.circuit.len()],
+        };
+        flow.waiting[self.start] = pulses.clone();
+
+        let settling = Settling {
+            circuit: self,
+            max_moves,
+            steps: Cell::new(0),
+        };
+        for nodes in settling.components(&self.reachable()) {
+            settling.settle(&nodes, &mut flow).map_err(|e| {
+                format!(
+                    "cannot settle the {} flip-flops of the cycle through {}: {e}",
+                    nodes.len(),
+                    self.names[nodes[0]]
+                )
+            })?;
+        }
+
+        let mut received = flow.received;
+        received[SIGNAL_OUT] = mem::take(&mut flow.waiting[SIGNAL_OUT]);
+        received[SIGNAL_BIN] = mem::take(&mut flow.waiting[SIGNAL_BIN]);
+        Ok(received)
+    }
+
+    /// Moves `k` pulses out of `node`, adding them to the pulses `waiting` at its outputs.
+    fn send(&self, node: SignalId, k: &BigUint, state: &mut [bool], waiting: &mut [BigUint]) {
+        let (half, odd) = k.halve();
+        let rounded_up = if odd {
+            &half + &BigUint::from(1u64)
+        } else {
+            half.clone()
+        };
+        let sent = if state[node] {
+            [half, rounded_up]
+        } else {
+            [rounded_up, half]
+        };
+        state[node] ^= odd;
+        for (&child, sent) in self.circuit[node].outputs.iter().zip(sent) {
+            waiting[child] += sent;
+        }
+    }
+
+    /// Pulses that each flip-flop of the cycle `nodes` receives at least, the others
+    /// having settled.
+    ///
+    /// Were the halves not rounded, the counts v would solve v = w + B v / 2, where w
+    /// counts the pulses waiting at each flip-flop and B the outputs from one flip-flop
+    /// of the cycle to another. The rounding moves each count by at most d/2 z, where d is
+    /// the most outputs that a flip-flop receives from the cycle and z solves
+    /// z = 1 + B z / 2. Settling from v - d z leaves a few pulses waiting, whichever way
+    /// the halves are rounded. `None` if the system is too large for machine integers.
+    fn lower_bounds(&self, nodes: &[SignalId], waiting: &[BigUint]) -> Option<Vec<BigUint>> {
+        if nodes.len() > MAX_SYSTEM {
+            return None;
+        }
+        let index: HashMap<SignalId, usize> =
+            (0..).zip(nodes).map(|(i, &node)| (node, i)).collect();
+
+        // (2 - B) v = 2 w
+        let mut matrix = vec![vec![0; nodes.len()]; nodes.len()];
+        let mut inputs = vec![0; nodes.len()];
+        for (i, &node) in nodes.iter().enumerate() {
+            matrix[i][i] += 2;
+            for child in &self.circuit[node].outputs {
+                if let Some(&j) = index.get(child) {
+                    matrix[j][i] -= 1;
+                    inputs[j] += 1;
+                }
+            }
+        }
+        let most_inputs = inputs.into_iter().max().unwrap_or(0);
+        let (det, adjugate) = adjugate(matrix)?;
+
+        // v - d z = adj (2 w - 2 d) / det, adj and det being non-negative
+        let det = BigUint::from(u128::try_from(det).ok()?);
+        adjugate
+            .iter()
+            .map(|row| {
+                let mut plus = BigUint::default();
+                let mut minus = 0u128;
+                for (&a, &node) in row.iter().zip(nodes) {
+                    let a = u128::try_from(a).ok()?.checked_mul(2)?;
+                    plus += &BigUint::from(a) * &waiting[node];
+                    minus = minus.checked_add(a.checked_mul(most_inputs)?)?;
+                }
+                Some(
+                    plus.checked_sub(&BigUint::from(minus))
+                        .map_or_else(BigUint::default, |bound| &bound / &det),
+                )
+            })
+            .collect()
+    }
+
+    /// Graphviz document of the circuit, the outputs of the flip-flops being labelled
+    /// 1 and 2, and the nodes annotated with `pulses` if any.
+    fn dot(&self, pulses: Option<&[Option<(u128, u128)>]>) -> String {
+        let mut dot = "digraph circuit {\n    node [shape=circle];\n".to_string();
+        for (id, name) in self.names.iter().enumerate() {
+            let style = match id {
+                SIGNAL_INP => " shape=invhouse style=filled fillcolor=lightblue",
+                SIGNAL_OUT => " shape=doublecircle style=filled fillcolor=palegreen",
+                SIGNAL_BIN => " shape=box style=filled fillcolor=lightgray",
+                _ => "",
+            };
+            let mut label = name.clone();
+            if let Some(Some((count, modulus))) = pulses.map(|pulses| pulses[id]) {
+                label += &format!("\n{count} (mod {modulus})");
+            }
+            dot += &format!("    {} [label={}{style}];\n", dot_id(name), dot_id(&label));
+        }
+        dot += &format!(
+            "    {} -> {};\n",
+            dot_id("INP"),
+            dot_id(&self.names[self.start])
+        );
+        for node in 3..self.circuit.len() {
+            for (k, &child) in (1..).zip(&self.circuit[node].outputs) {
+                dot += &format!(
+                    "    {} -> {} [label=\"{k}\"];\n",
+                    dot_id(&self.names[node]),
+                    dot_id(&self.names[child])
+                );
+            }
+        }
+        dot + "}\n"
     }
 }
 
-fn total_mod(base: u128, exp: u32, modulus: u128) -> u128 {
-    mod_pow_safe(base, exp, modulus)
+/// Pulses moving through a circuit, by signal.
+#[derive(Clone)]
+struct Flow {
+    /// of the flip-flops
+    state: Vec<bool>,
+    /// to move out of the flip-flops, or reaching OUT and BIN
+    waiting: Vec<BigUint>,
+    /// moved out of the flip-flops so far
+    received: Vec<BigUint>,
 }
 
-fn split_mod(n_mod: u128, mod_target: u128, pick_first: bool) -> u128 {
-    let base = mod_target * 2;
-    let n_reduced = n_mod % base;
-    let q = n_reduced / 2;
-    let r = n_reduced % 2;
-    if pick_first {
-        (q + r) % mod_target
-    } else {
-        q % mod_target
-    }
+/// Pulses of a circuit moving until they leave its strongly connected components.
+struct Settling<'a> {
+    circuit: &'a Circuit,
+    /// made move by move in a cycle before settling it through one of its flip-flops
+    max_moves: usize,
+    /// of the settling taken so far, up to `MAX_SETTLING`
+    steps: Cell<usize>,
 }
 
-// Multiplication modulaire (a * b) % m sans overflow u128
-fn mul_mod(mut a: u128, mut b: u128, m: u128) -> u128 {
-    let mut res = 0;
-    a %= m;
-    while b > 0 {
-        if b % 2 == 1 {
-            res = (res + a) % m; // Addition au lieu de multiplication
+impl Settling<'_> {
+    /// Counts `steps` steps of the settling, failing past `MAX_SETTLING`.
+    fn step(&self, steps: usize) -> Result<(), String> {
+        self.steps.set(self.steps.get() + steps);
+        if self.steps.get() > MAX_SETTLING {
+            return Err(format!("settling takes more than {MAX_SETTLING} steps"));
+        }
+        Ok(())
+    }
+
+    /// Strongly connected components of the circuit restricted to `nodes`, from the
+    /// sources onwards.
+    fn components(&self, nodes: &[SignalId]) -> Vec<Vec<SignalId>> {
+        let circuit = &self.circuit.circuit;
+        let mut inside = vec![false; circuit.len()];
+        for &node in nodes {
+            inside[node] = true;
+        }
+        let mut graph = Graph::new(circuit.len());
+        for &node in nodes {
+            for &child in &circuit[node].outputs {
+                if inside[child] {
+                    graph.add_edge(node, child);
+                }
+            }
+        }
+
+        let (component, count) = graph::strongly_connected_components(&graph);
+        let mut components = vec![Vec::new(); count];
+        for &node in nodes {
+            components[component[node]].push(node);
+        }
+        // components are numbered against the edges
+        components.retain(|nodes| !nodes.is_empty());
+        components.reverse();
+        components
+    }
+
+    /// Moves the pulses waiting at the flip-flops of the component `nodes` until they
+    /// all leave it. The pulses entering a cycle first move by the counts of
+    /// `lower_bounds`, the few left then settling move by move, or through
+    /// `settle_pivot` once they take more than `max_moves`.
+    fn settle(&self, nodes: &[SignalId], flow: &mut Flow) -> Result<(), String> {
+        let circuit = self.circuit;
+        let first = nodes[0];
+        if nodes.len() == 1 && !circuit.circuit[first].outputs.contains(&first) {
+            self.step(1)?;
+            let k = mem::take(&mut flow.waiting[first]);
+            circuit.send(first, &k, &mut flow.state, &mut flow.waiting);
+            flow.received[first] += k;
+            return Ok(());
+        }
+
+        if nodes.len() <= MAX_SYSTEM {
+            self.step(nodes.len().pow(3))?;
+        }
+        if let Some(bounds) = circuit.lower_bounds(nodes, &flow.waiting) {
+            for (&node, k) in nodes.iter().zip(&bounds) {
+                self.step(1)?;
+                circuit.send(node, k, &mut flow.state, &mut flow.waiting);
+            }
+            for (&node, k) in nodes.iter().zip(bounds) {
+                flow.waiting[node] = flow.waiting[node].checked_sub(&k).ok_or_else(|| {
+                    format!(
+                        "the lower bound of {} exceeds the pulses it receives",
+                        circuit.names[node]
+                    )
+                })?;
+                flow.received[node] += k;
+            }
+        }
+
+        self.step(circuit.circuit.len())?;
+        let mut moved = flow.clone();
+        if self.settle_moves(nodes, &mut moved)? {
+            *flow = moved;
+            Ok(())
+        } else {
+            self.settle_pivot(nodes, flow)
+        }
+    }
+
+    /// Moves the pulses waiting at the flip-flops of the cycle `nodes` until they all
+    /// leave it, `false` if they take more than `max_moves`.
+    fn settle_moves(&self, nodes: &[SignalId], flow: &mut Flow) -> Result<bool, String> {
+        let circuit = self.circuit;
+        let mut queued = vec![false; circuit.circuit.len()];
+        for &node in nodes {
+            queued[node] = true;
+        }
+        let inside = queued.clone();
+        let mut queue: VecDeque<SignalId> = nodes.iter().copied().collect();
+
+        let mut moves = 0;
+        while let Some(node) = queue.pop_front() {
+            queued[node] = false;
+            let k = mem::take(&mut flow.waiting[node]);
+            if k.is_zero() {
+                continue;
+            }
+            moves += 1;
+            if moves > self.max_moves {
+                return Ok(false);
+            }
+            self.step(1)?;
+            circuit.send(node, &k, &mut flow.state, &mut flow.waiting);
+            flow.received[node] += k;
+
+            for &child in &circuit.circuit[node].outputs {
+                if inside[child] && !queued[child] && !flow.waiting[child].is_zero() {
+                    queued[child] = true;
+                    queue.push_back(child);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Settles the cycle `nodes` through the flip-flop receiving the most outputs from
+    /// it, the pivot: it moves the least count t of pulses such that, the others settling
+    /// with the pivot held, it receives at most t pulses in all. Its pulses then all
+    /// leave. One more pulse moving out of the pivot comes back to it at most once, so
+    /// the counts passing the test are those from the least one onwards: it is searched
+    /// by doubling, then bisecting.
+    fn settle_pivot(&self, nodes: &[SignalId], flow: &mut Flow) -> Result<(), String> {
+        let circuit = self.circuit;
+        let inputs = |node: SignalId| {
+            nodes
+                .iter()
+                .flat_map(|&other| circuit.circuit[other].outputs)
+                .filter(|&child| child == node)
+                .count()
+        };
+        let pivot = nodes
+            .iter()
+            .copied()
+            .max_by_key(|&node| inputs(node))
+            .ok_or("a cycle without flip-flops")?;
+        let others: Vec<SignalId> = nodes.iter().copied().filter(|&n| n != pivot).collect();
+        let others = self.components(&others);
+        let waiting = mem::take(&mut flow.waiting[pivot]);
+
+        let attempt = |moves: &BigUint| -> Result<Option<Flow>, String> {
+            self.step(circuit.circuit.len() + 1)?;
+            let mut attempt = flow.clone();
+            circuit.send(pivot, moves, &mut attempt.state, &mut attempt.waiting);
+            attempt.received[pivot] += moves.clone();
+            for nodes in &others {
+                self.settle(nodes, &mut attempt)?;
+            }
+            let received = &waiting + &mem::take(&mut attempt.waiting[pivot]);
+            Ok((received <= *moves).then_some(attempt))
+        };
+
+        let mut low = BigUint::default();
+        if let Some(settled) = attempt(&low)? {
+            *flow = settled;
+            return Ok(());
         }
-        a = (a * 2) % m; // Doublement au lieu de carré
-        b /= 2;
+        let mut high = waiting.clone().max(BigUint::from(1u64));
+        let mut settled = loop {
+            match attempt(&high)? {
+                Some(settled) => break settled,
+                None => {
+                    let doubled = &high + &high;
+                    low = mem::replace(&mut high, doubled);
+                }
+            }
+        };
+        // low fails and high passes
+        loop {
+            let middle = (&low + &high).halve().0;
+            if middle == low {
+                break;
+            }
+            match attempt(&middle)? {
+                Some(attempt) => (high, settled) = (middle, attempt),
+                None => low = middle,
+            }
+        }
+        *flow = settled;
+        Ok(())
     }
-    res
 }
 
-fn mod_pow_safe(mut base: u128, mut exp: u32, modulus: u128) -> u128 {
-    if modulus == 1 {
-        return 0;
+/// Determinant and adjugate of a square matrix whose leading principal minors are not
+/// zero (fraction-free Gauss-Jordan elimination), `None` if they overflow.
+fn adjugate(mut matrix: Vec<Vec<i128>>) -> Option<(i128, Vec<Vec<i128>>)> {
+    let n = matrix.len();
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row.extend((0..n).map(|j| i128::from(i == j)));
     }
-    let mut result = 1;
-    base %= modulus;
 
-    while exp > 0 {
-        if exp % 2 == 1 {
-            result = mul_mod(result, base, modulus);
+    // every entry stays a minor of [matrix | identity], divided exactly by the last pivot
+    let mut previous = 1;
+    for k in 0..n {
+        let pivot = matrix[k][k];
+        if pivot == 0 {
+            return None;
+        }
+        for i in (0..n).filter(|&i| i != k) {
+            let factor = matrix[i][k];
+            for j in (0..2 * n).filter(|&j| j != k) {
+                let entry = pivot
+                    .checked_mul(matrix[i][j])?
+                    .checked_sub(factor.checked_mul(matrix[k][j])?)?;
+                matrix[i][j] = entry / previous;
+            }
+            matrix[i][k] = 0;
         }
-        base = mul_mod(base, base, modulus);
-        exp /= 2;
+        previous = pivot;
     }
-    result
+
+    let adjugate = matrix.into_iter().map(|row| row[n..].to_vec()).collect();
+    Some((previous, adjugate))
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// Quoted Graphviz identifier.
+fn dot_id(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
 
-    const TEST_INPUT: &str = "\
+fn total_mod(base: u128, exp: u32, modulus: u128) -> u128 {
+    Modulus::new(modulus).pow(base, u64::from(exp))
+}
+
+fn split_mod(n_mod: u128, mod_target: u128, pick_first: bool) -> u128 {
+    let base = mod_target * 2;
+    let n_reduced = n_mod % base;
+    let q = n_reduced / 2;
+    let r = n_reduced % 2;
+    if pick_first {
+        (q + r) % mod_target
+    } else {
+        q % mod_target
+    }
+}
+
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 INP: abc
 abc: def ghi
 def: OUT ghi
 ghi: OUT BIN
 ";
 
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::property::{self, Source};
+    use crate::rng::Rng;
+
     #[test]
     fn test_part1() {
-        let mut circuit = Circuit::from(TEST_INPUT);
+        let mut circuit = Circuit::parse(EXAMPLE).unwrap();
         assert_eq!(circuit.run(6), 4);
+        assert_eq!(part1(EXAMPLE), Ok(BigUint::from(77160u64)));
+
+        // two pulses out of three reach OUT, after going around the cycle once
+        let input = "INP: abc\nabc: def OUT\ndef: abc BIN\n";
+        assert_eq!(part1(input), Ok(BigUint::from(82304u64)));
+        let circuit = Circuit::parse(input).unwrap();
+        let pulses = circuit.exact_pulses(&BigUint::from(123456u64)).unwrap();
+        assert_eq!(pulses[SIGNAL_OUT], BigUint::from(82304u64));
+        assert_eq!(pulses[SIGNAL_BIN], BigUint::from(41152u64));
+        assert_eq!(
+            exact(input).map(|count| count.abbreviated()),
+            Ok("2980540871...2184761344 (3730 digits)".to_string())
+        );
     }
 
     #[test]
     fn test_part2() {
-        let circuit = Circuit::from(TEST_INPUT);
-        assert_eq!(circuit.solve(12, 3456, 10u128.pow(15)), 660414548213760);
+        let circuit = Circuit::parse(EXAMPLE).unwrap();
+        assert_eq!(circuit.solve(12, 3456, 10u128.pow(15)), Ok(660414548213760));
+    }
+
+    #[test]
+    fn test_explain() {
+        let circuit = Circuit::parse(EXAMPLE).unwrap();
+        let messages = trace::capture(trace::Level::Info, || {
+            assert_eq!(circuit.solve(2, 3, 1000), Ok(5));
+        });
+        assert_eq!(
+            messages,
+            [
+                "abc: 8 pulses (mod 8000)",
+                "def: 4 pulses (mod 4000)",
+                "ghi: 6 pulses (mod 2000)",
+                "OUT: 5 pulses (mod 1000)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exact_count() {
+        // a chain too deep to reduce by 10^15 along the way
+        let mut lines = vec!["INP: f0".to_string()];
+        lines.extend((0..100).map(|i| format!("f{i}: f{} OUT", i + 1)));
+        lines.push("f100: OUT BIN".to_string());
+        let input = lines.join("\n");
+        let expected = Circuit::parse(&input).unwrap().run(3usize.pow(7));
+        let circuit = Circuit::parse(&input).unwrap();
+        let messages = trace::capture(trace::Level::Info, || {
+            assert_eq!(circuit.solve(3, 7, 10u128.pow(15)), Ok(expected.into()));
+        });
+        assert_eq!(
+            messages[0],
+            "circuit too deep for modulus 1000000000000000: counting the pulses exactly"
+        );
+    }
+
+    #[test]
+    fn test_exact() {
+        let count = exact(EXAMPLE).unwrap();
+        assert_eq!(count.abbreviated(), "2794257066...4548213760 (3730 digits)");
+        assert_eq!(
+            &count % &BigUint::from(10u128.pow(15)),
+            BigUint::from(660414548213760u64)
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        let circuit = Circuit::parse(EXAMPLE).unwrap();
+        let dot = circuit.dot(None);
+        assert!(dot.starts_with("digraph circuit {\n"));
+        assert!(dot.contains("    \"INP\" [label=\"INP\" shape=invhouse"));
+        assert!(dot.contains("    \"OUT\" [label=\"OUT\" shape=doublecircle"));
+        assert!(dot.contains("    \"abc\" [label=\"abc\"];\n"));
+        assert!(dot.contains("    \"INP\" -> \"abc\";\n"));
+        assert!(dot.contains("    \"def\" -> \"OUT\" [label=\"1\"];\n"));
+        assert!(dot.contains("    \"def\" -> \"ghi\" [label=\"2\"];\n"));
+        assert!(dot.ends_with("}\n"));
+
+        let dot = circuit.dot(Some(&circuit.pulses(2, 3, 1000).unwrap()));
+        assert!(dot.contains("    \"abc\" [label=\"abc\\n8 (mod 8000)\"];\n"));
+        assert!(dot.contains("[label=\"OUT\\n5 (mod 1000)\" shape=doublecircle"));
+        assert!(dot.contains("    \"BIN\" [label=\"BIN\" shape=box"));
+
+        assert_eq!(dot_id("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    }
+
+    #[test]
+    fn test_inspect() {
+        let mut report = Report::new(EXAMPLE);
+        assert_eq!(inspect(EXAMPLE, &mut report), Ok(()));
+        assert!(report.is_valid());
+        assert!(report.to_string().contains("  longest path to OUT  3\n"));
+
+        let input = "INP: abc\nabc: def OUT\ndef: abc BIN\n";
+        let mut report = Report::new(input);
+        assert_eq!(inspect(input, &mut report), Ok(()));
+        assert!(
+            report.to_string().contains(
+                "  longest path to OUT  unbounded, the circuit has a cycle through abc\n"
+            )
+        );
+        assert!(report.is_valid());
+
+        let input = "INP: abc\nabc: BIN BIN\n";
+        let mut report = Report::new(input);
+        assert_eq!(inspect(input, &mut report), Ok(()));
+        assert!(
+            report
+                .to_string()
+                .contains("OUT cannot be reached from INP")
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Circuit::parse("abc: def ghi\n").is_err());
+        assert!(Circuit::parse("INP: abc\nabc: OUT\n").is_err());
+        assert!(Circuit::parse("INP: abc\nabc: OUT def\n").is_err());
+        assert!(Circuit::parse("INP: abc\nabc: OUT def\ndef: def def\n").is_err());
+        assert!(Circuit::parse("INP: abc\nabc: OUT BIN BIN\n").is_err());
+        assert_eq!(
+            Circuit::parse("INP: abc\nabc: OUT\n").err(),
+            Some("line 2, column 9: expected a word, found end of line".to_string())
+        );
     }
 
     #[test]
@@ -309,4 +1046,251 @@ ghi: OUT BIN
             444320153330481056663277142016
         );
     }
+
+    /// Acyclic circuit: flip-flop `i` only feeds flip-flops `j > i`, OUT or BIN.
+    fn circuit(s: &mut Source) -> String {
+        let n = s.int(1usize..=6);
+        let mut lines = vec!["INP: f0".to_string()];
+        for i in 0..n {
+            let outputs: Vec<String> = (0..2)
+                .map(|_| match s.int(0..=n - i) {
+                    0 => "OUT".to_string(),
+                    1 => "BIN".to_string(),
+                    k => format!("f{}", i + k - 1),
+                })
+                .collect();
+            lines.push(format!("f{i}: {} {}", outputs[0], outputs[1]));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_part2_matches_simulation() {
+        property::check(
+            |s| {
+                (
+                    circuit(s),
+                    s.int(1u32..=5),
+                    s.int(0u32..=4),
+                    s.int(1u64..=1000),
+                )
+            },
+            |(input, base, exp, modulus)| {
+                let pulses = base.pow(*exp) as usize;
+                let mut circuit = Circuit::parse(input).unwrap();
+                let expected = circuit.run(pulses) as u128 % *modulus as u128;
+                let circuit = Circuit::parse(input).unwrap();
+                circuit.solve(*base as u128, *exp, *modulus as u128) == Ok(expected)
+            },
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        // every other pulse comes back from def to abc
+        let input = "INP: abc\nabc: def OUT\ndef: abc BIN\n";
+        let circuit = Circuit::parse(input).unwrap();
+        let messages = trace::capture(trace::Level::Info, || {
+            assert_eq!(circuit.solve(2, 3, 1000), Ok(5));
+        });
+        assert_eq!(
+            messages,
+            [
+                "the circuit has a cycle through abc: counting the pulses exactly",
+                "abc: 11 pulses (mod 1000)",
+                "def: 6 pulses (mod 1000)",
+                "OUT: 5 pulses (mod 1000)",
+            ]
+        );
+        assert_eq!(Circuit::parse(input).unwrap().run(8), 5);
+
+        assert!(circuit.solve(12, 3456, 10u128.pow(15)).is_ok());
+
+        // pulses cannot leave a cycle without an exit
+        assert_eq!(
+            Circuit::parse("INP: abc\nabc: def OUT\ndef: ghi ghi\nghi: def def\n").err(),
+            Some("a pulse reaching def never leaves the circuit".to_string())
+        );
+    }
+
+    /// Loop of `n` flip-flops sending every other pulse back to the first one, the last
+    /// one ending with `last`.
+    fn feedback_loop(n: usize, last: &str) -> String {
+        let mut lines = vec!["INP: f0".to_string()];
+        lines.extend((0..n - 1).map(|i| format!("f{i}: f{} f0", i + 1)));
+        lines.push(format!("f{}: {last}", n - 1));
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_feedback_loops() {
+        // every pulse ends at OUT, through up to 2^12 moves of a pulse around the loop
+        let input = feedback_loop(12, "OUT f0");
+        let circuit = Circuit::parse(&input).unwrap();
+        assert_eq!(
+            circuit.solve(12, 3456, 10u128.pow(15)),
+            Ok(total_mod(12, 3456, 10u128.pow(15)))
+        );
+        let pulses = circuit.exact_pulses(&BigUint::from(5000u64)).unwrap();
+        let mut simulated = Circuit::parse(&input).unwrap();
+        assert_eq!(simulated.run(5000), 5000);
+        assert_eq!(pulses[SIGNAL_OUT], BigUint::from(5000u64));
+
+        let input = feedback_loop(12, "OUT BIN");
+        let circuit = Circuit::parse(&input).unwrap();
+        assert!(circuit.solve(12, 3456, 10u128.pow(15)).is_ok());
+        let expected = Circuit::parse(&input).unwrap().run(5000);
+        assert_eq!(circuit.solve(5000, 1, 10u128.pow(15)), Ok(expected.into()));
+
+        // a pulse takes 2^40 moves to leave
+        let circuit = Circuit::parse(&feedback_loop(40, "OUT f0")).unwrap();
+        assert_eq!(
+            circuit.solve(12, 3456, 10u128.pow(15)),
+            Ok(total_mod(12, 3456, 10u128.pow(15)))
+        );
+        let pulses = circuit.exact_pulses(&BigUint::from(3u64)).unwrap();
+        assert_eq!(pulses[SIGNAL_OUT], BigUint::from(3u64));
+        // the first pulse goes straight through, the others make f0 count to 2^40 twice
+        assert_eq!(pulses[3], BigUint::from((2u64 << 40) + 1));
+    }
+
+    #[test]
+    fn test_deep_feedback_loop() {
+        let input = feedback_loop(30, "OUT f0");
+        assert_eq!(part1(&input), Ok(BigUint::from(123456u64)));
+        assert_eq!(part2(&input), Ok(total_mod(12, 3456, 10u128.pow(15))));
+        assert!(solve(&input).is_ok());
+
+        let input = feedback_loop(30, "BIN OUT");
+        let pulses = Circuit::parse(&input)
+            .unwrap()
+            .exact_pulses(&BigUint::from(123456u64))
+            .unwrap();
+        assert_eq!(part1(&input), Ok(pulses[SIGNAL_OUT].clone()));
+        assert_eq!(
+            &pulses[SIGNAL_OUT] + &pulses[SIGNAL_BIN],
+            BigUint::from(123456u64)
+        );
+    }
+
+    /// Circuit where flip-flops may feed any flip-flop, pulses possibly never leaving it.
+    fn cyclic_circuit(s: &mut Source) -> String {
+        let n = s.int(1usize..=6);
+        let mut lines = vec!["INP: f0".to_string()];
+        for i in 0..n {
+            let outputs: Vec<String> = (0..2)
+                .map(|_| match s.int(0..=n + 1) {
+                    0 => "OUT".to_string(),
+                    1 => "BIN".to_string(),
+                    k => format!("f{}", k - 2),
+                })
+                .collect();
+            lines.push(format!("f{i}: {} {}", outputs[0], outputs[1]));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_cyclic_part2_matches_simulation() {
+        property::check(
+            |s| (cyclic_circuit(s), s.int(0u32..=4), s.int(1u64..=1000)),
+            |(input, exp, modulus)| {
+                let Ok(mut circuit) = Circuit::parse(input) else {
+                    return true;
+                };
+                let expected = circuit.run(5usize.pow(*exp)) as u128 % *modulus as u128;
+                let circuit = Circuit::parse(input).unwrap();
+                circuit.solve(5, *exp, *modulus as u128) == Ok(expected)
+            },
+        );
+    }
+
+    #[test]
+    fn test_pivot_matches_simulation() {
+        // every cycle is settled through a pivot
+        property::check(
+            |s| (cyclic_circuit(s), s.int(0usize..=200)),
+            |(input, n)| {
+                let Ok(mut circuit) = Circuit::parse(input) else {
+                    return true;
+                };
+                let expected = circuit.run(*n);
+                let circuit = Circuit::parse(input).unwrap();
+                let pulses = circuit
+                    .exact_pulses_within(&BigUint::from(*n as u64), 0)
+                    .unwrap();
+                pulses[SIGNAL_OUT] == BigUint::from(u64::from(expected))
+            },
+        );
+    }
+
+    /// Circuit of 50 to 60 flip-flops feeding any flip-flop, most of them being in one
+    /// strongly connected component.
+    fn large_cyclic_circuit(seed: u64) -> String {
+        let mut rng = Rng::new(seed);
+        let n = 50 + rng.below(11);
+        let mut lines = vec!["INP: f0".to_string()];
+        for i in 0..n {
+            let outputs: Vec<String> = (0..2)
+                .map(|_| match rng.below(n + 2) {
+                    0 => "OUT".to_string(),
+                    1 => "BIN".to_string(),
+                    k => format!("f{}", k - 2),
+                })
+                .collect();
+            lines.push(format!("f{i}: {} {}", outputs[0], outputs[1]));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_large_cycle() {
+        // cycles of 42 flip-flops, too many to bound through a linear system
+        let input = large_cyclic_circuit(3);
+        let expected = Circuit::parse(&input).unwrap().run(123456);
+        assert_eq!(part1(&input), Ok(BigUint::from(u64::from(expected))));
+
+        // the pulses take too many moves to run one by one
+        let input = large_cyclic_circuit(32);
+        let expected = Circuit::parse(&input).unwrap().run(123456);
+        let messages = trace::capture(trace::Level::Info, || {
+            assert_eq!(part1(&input), Ok(BigUint::from(u64::from(expected))));
+        });
+        assert_eq!(
+            messages,
+            ["the pulses take more than 16777216 moves: counting them exactly"]
+        );
+
+        // each attempt of a pivot settles the cycles that it leaves through pivots of their
+        // own, many times over for 10^30 pulses
+        let circuit = Circuit::parse(&input).unwrap();
+        let error = circuit
+            .exact_pulses(&BigUint::from(10u64).pow(30))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "cannot settle the 42 flip-flops of the cycle through f0: \
+             settling takes more than 4194304 steps"
+        );
+    }
+
+    #[test]
+    fn test_large_cycle_mod() {
+        // counted exactly before being reduced, the cycles being too large to settle
+        let circuit = Circuit::parse(&large_cyclic_circuit(32)).unwrap();
+        assert_eq!(
+            circuit.solve(10, 30, 10u128.pow(15)),
+            Err("cannot settle the 42 flip-flops of the cycle through f0: \
+                 settling takes more than 4194304 steps"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn test_part1_bounded_by_pulses() {
+        property::check(
+            |s| (circuit(s), s.int(0usize..=100)),
+            |(input, n)| Circuit::parse(input).unwrap().run(*n) as usize <= *n,
+        );
+    }
 }
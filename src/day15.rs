@@ -4,27 +4,52 @@
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day15.txt").ok()?;
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+use crate::parse;
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let events = parse_input(input)?;
+    Ok((part1(&events).to_string(), part2(&events).to_string()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Event {
+    /// a toy of this quality
+    Receive(i64),
+    Request,
+}
+
+fn parse_input(input: &str) -> Result<Vec<Event>, String> {
+    let mut stock = 0;
+
+    parse::lines(input)
+        .map(|mut line| {
+            let start = line;
+            let event = match line.word()? {
+                "receive" => {
+                    stock += 1;
+                    Event::Receive(i64::from(line.int::<u32>()?))
+                }
+                "request" if stock == 0 => return Err(start.error("request with no toy in stock")),
+                "request" => {
+                    stock -= 1;
+                    Event::Request
+                }
+                word => return Err(start.error(format!("unknown event {word:?}"))),
+            };
+            line.end()?;
+            Ok(event)
+        })
+        .collect()
 }
 
-fn part1(input: &str) -> u64 {
+fn part1(events: &[Event]) -> u64 {
     let mut lower: BinaryHeap<i64> = BinaryHeap::new();
     let mut upper: BinaryHeap<Reverse<i64>> = BinaryHeap::new();
     let mut satisfaction_score = 0;
     let mut request_count = 0;
 
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-
-        if line.starts_with("receive") {
-            let val_str = line.strip_prefix("receive ").unwrap();
-            let val: i64 = val_str.parse().unwrap();
-
+    for &event in events {
+        if let Event::Receive(val) = event {
             // Always push to lower (max-heap) first
             lower.push(val);
 
@@ -39,7 +64,7 @@ fn part1(input: &str) -> u64 {
             {
                 lower.push(min_upper);
             }
-        } else if line == "request" {
+        } else {
             request_count += 1;
             let median = if lower.len() > upper.len() {
                 lower.pop().unwrap()
@@ -55,7 +80,7 @@ fn part1(input: &str) -> u64 {
     satisfaction_score
 }
 
-fn part2(input: &str) -> u64 {
+fn part2(events: &[Event]) -> u64 {
     // (quality, count)
     // lower is a max-heap (by quality)
     let mut lower: BinaryHeap<(i64, u64)> = BinaryHeap::new();
@@ -68,15 +93,8 @@ fn part2(input: &str) -> u64 {
     let mut satisfaction_score = 0;
     let mut request_count = 0;
 
-    for line in input.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-
-        if line.starts_with("receive") {
-            let val_str = line.strip_prefix("receive ").unwrap();
-            let val: i64 = val_str.parse().unwrap();
+    for &event in events {
+        if let Event::Receive(val) = event {
             let count = val as u64; // Receive x copies
 
             // Determine where to add
@@ -129,7 +147,7 @@ fn part2(input: &str) -> u64 {
                     count_upper += diff;
                 }
             }
-        } else if line == "request" {
+        } else {
             request_count += 1;
             let total = count_lower + count_upper;
             if total == 0 {
@@ -219,11 +237,8 @@ fn part2(input: &str) -> u64 {
     satisfaction_score
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    const TEST_INPUT: &str = "\
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 receive 4
 receive 9
 receive 2
@@ -234,13 +249,75 @@ receive 99999
 request
 request";
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 400040);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 400040);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 700020);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 700020);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input("receive 1\nrequest\nrequest"),
+            Err("line 3, column 1: request with no toy in stock".to_string())
+        );
+        assert!(parse_input("receive -1").is_err());
+        assert!(parse_input("receive").is_err());
+        assert!(parse_input("return 1").is_err());
+    }
+
+    /// Log of toy receptions, with a request only when a toy is available.
+    fn log(s: &mut Source) -> Vec<String> {
+        let mut available = 0;
+        s.vec(0..=30, |s| {
+            if available > 0 && s.bool() {
+                available -= 1;
+                "request".to_string()
+            } else {
+                available += 1;
+                format!("receive {}", s.int(1i64..=20))
+            }
+        })
+    }
+
+    /// Scores the log by sorting the toys, `copies` giving how many toys a reception adds.
+    fn score(log: &[String], copies: fn(i64) -> usize) -> u64 {
+        let mut toys: Vec<i64> = Vec::new();
+        let mut requests = 0;
+        let mut score = 0;
+        for line in log {
+            if let Some(quality) = line.strip_prefix("receive ") {
+                let quality = quality.parse().unwrap();
+                toys.extend(std::iter::repeat_n(quality, copies(quality)));
+                toys.sort();
+            } else {
+                requests += 1;
+                score += requests * toys.remove(toys.len() / 2) as u64;
+            }
+        }
+        score
+    }
+
+    #[test]
+    fn test_part1_matches_sorting() {
+        property::check(log, |log| {
+            part1(&parse_input(&log.join("\n")).unwrap()) == score(log, |_| 1)
+        });
+    }
+
+    #[test]
+    fn test_part2_matches_sorting() {
+        property::check(log, |log| {
+            part2(&parse_input(&log.join("\n")).unwrap()) == score(log, |quality| quality as usize)
+        });
     }
 }
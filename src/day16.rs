@@ -1,54 +1,44 @@
 // Day 16: Remedial Lessons
 // https://lovemathboy.github.io/day16.html
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day16.txt").ok()?;
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+use crate::parse;
+use crate::trace;
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let lessons = parse_input(input)?;
+    Ok((part1(&lessons).to_string(), part2(&lessons).to_string()))
 }
 
 #[derive(Debug, Clone, Copy)]
 struct Lesson {
+    number: u32,
     start: u64,
     end: u64,
 }
 
-fn parse_line(line: &str) -> Option<Lesson> {
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() != 13 {
-        return None;
-    }
-
-    // "Lesson #1: Starts at t = 471925571 and ends at t = 481914514"
-    // 0: Lesson
-    // 1: #1:
-    // 2: Starts
-    // 3: at
-    // 4: t
-    // 5: =
-    // 6: 471925571
-    // 7: and
-    // 8: ends
-    // 9: at
-    // 10: t
-    // 11: =
-    // 12: 481914514
-
-    let start = parts[6].parse().ok()?;
-    let end = parts[12].parse().ok()?;
-
-    Some(Lesson { start, end })
-}
-
-fn parse_input(input: &str) -> Vec<Lesson> {
-    input
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .filter_map(parse_line)
+/// "Lesson #1: Starts at t = 471925571 and ends at t = 481914514"
+fn parse_input(input: &str) -> Result<Vec<Lesson>, String> {
+    parse::lines(input)
+        .map(|mut line| {
+            line.literal("Lesson #")?;
+            let number = line.int()?;
+            line.literal(": Starts at t =")?;
+            let start = line.int()?;
+            let at_end = line;
+            line.literal(" and ends at t =")?;
+            let end = line.int()?;
+            line.end()?;
+
+            if end < start {
+                return Err(at_end.error("the lesson ends before it starts"));
+            }
+            Ok(Lesson { number, start, end })
+        })
         .collect()
 }
 
-fn part1(input: &str) -> u64 {
-    let mut lessons = parse_input(input);
+fn part1(lessons: &[Lesson]) -> u64 {
+    let mut lessons = lessons.to_vec();
     // Sort by end time
     lessons.sort_by_key(|l| l.end);
 
@@ -57,6 +47,12 @@ fn part1(input: &str) -> u64 {
 
     for lesson in lessons {
         if lesson.start >= last_end {
+            trace::info!(
+                "lesson #{} attended, from t = {} to {}",
+                lesson.number,
+                lesson.start,
+                lesson.end
+            );
             count += 1;
             last_end = lesson.end;
         }
@@ -65,8 +61,7 @@ fn part1(input: &str) -> u64 {
     count
 }
 
-fn part2(input: &str) -> u64 {
-    let lessons = parse_input(input);
+fn part2(lessons: &[Lesson]) -> u64 {
     let mut events = Vec::new();
 
     for lesson in lessons {
@@ -84,21 +79,23 @@ fn part2(input: &str) -> u64 {
     let mut max_overlap = 0;
     let mut current_overlap = 0;
 
-    for (_, type_) in events {
+    let mut busiest = 0;
+
+    for (time, type_) in events {
         current_overlap += type_;
         if current_overlap > max_overlap {
             max_overlap = current_overlap;
+            busiest = time;
         }
     }
 
+    trace::info!("{max_overlap} lessons at the same time from t = {busiest}");
+
     max_overlap as u64
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    const TEST_INPUT: &str = "\
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 Lesson #1: Starts at t = 0 and ends at t = 20
 Lesson #2: Starts at t = 50 and ends at t = 150
 Lesson #3: Starts at t = 180 and ends at t = 200
@@ -107,19 +104,86 @@ Lesson #5: Starts at t = 10 and ends at t = 40
 Lesson #6: Starts at t = 30 and ends at t = 170
 Lesson #7: Starts at t = 160 and ends at t = 190";
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
     #[test]
     fn test_parse() {
-        let lessons = parse_input(TEST_INPUT);
+        let lessons = parse_input(EXAMPLE).unwrap();
         assert_eq!(lessons.len(), 7);
+        assert_eq!((lessons[1].start, lessons[1].end), (50, 150));
+
+        assert_eq!(
+            parse_input("Lesson #1: Starts at t = 5 and ends at t = 2").err(),
+            Some("line 1, column 27: the lesson ends before it starts".to_string())
+        );
+        assert!(parse_input("Lesson #1: Starts at t = 5").is_err());
+        assert!(parse_input("Lesson 1: Starts at t = 5 and ends at t = 6").is_err());
     }
 
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 4);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 4);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 2);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 2);
+    }
+
+    #[test]
+    fn test_explain() {
+        let lessons = parse_input(EXAMPLE).unwrap();
+        let messages = trace::capture(trace::Level::Info, || {
+            part1(&lessons);
+            part2(&lessons);
+        });
+        assert_eq!(
+            messages,
+            [
+                "lesson #1 attended, from t = 0 to 20",
+                "lesson #2 attended, from t = 50 to 150",
+                "lesson #7 attended, from t = 160 to 190",
+                "lesson #4 attended, from t = 190 to 240",
+                "2 lessons at the same time from t = 10",
+            ]
+        );
+    }
+
+    fn lessons(s: &mut Source) -> Vec<(u64, u64)> {
+        s.vec(0..=20, |s| {
+            let start = s.int(0u64..=100);
+            (start, start + s.int(1u64..=50))
+        })
+    }
+
+    fn schedule(lessons: &[(u64, u64)]) -> String {
+        lessons
+            .iter()
+            .enumerate()
+            .map(|(i, (start, end))| {
+                format!(
+                    "Lesson #{}: Starts at t = {start} and ends at t = {end}\n",
+                    i + 1
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_properties() {
+        property::check(lessons, |lessons| {
+            let n = lessons.len() as u64;
+            let lessons = parse_input(&schedule(lessons)).unwrap();
+            let (attended, tracks) = (part1(&lessons), part2(&lessons));
+
+            // each track is a set of compatible lessons
+            (n == 0 || (attended >= 1 && tracks >= 1))
+                && attended <= n
+                && tracks <= n
+                && n <= attended * tracks
+        });
     }
 }
@@ -3,87 +3,57 @@
 
 use std::collections::HashMap;
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day17.txt").ok()?;
-    Some((part1(&input).to_string(), part2(&input).to_string()))
-}
-
-fn parse_input(input: &str) -> HashMap<u32, Vec<u32>> {
-    let mut edges = HashMap::new();
-    for line in input.lines() {
-        let (from, to) = line.split_once(" -> ").unwrap();
-        let from = from.parse::<u32>().unwrap();
-        let to = to.parse::<u32>().unwrap();
-        edges.entry(from).or_insert_with(Vec::new).push(to);
-    }
+use crate::graph::{self, Graph};
+use crate::parse;
 
-    edges
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let graph = parse_input(input)?;
+    Ok((part1(&graph)?.to_string(), part2(&graph).to_string()))
 }
 
-fn part1(input: &str) -> u32 {
-    let edges = parse_input(input);
-    let mut memo = HashMap::new();
-    let mut max_len = 0;
-
-    for &start_node in edges.keys() {
-        max_len = max_len.max(longest_path(start_node, &edges, &mut memo));
+/// Graph of the network, its nodes being numbered in order of appearance.
+fn parse_input(input: &str) -> Result<Graph, String> {
+    let mut index: HashMap<u32, usize> = HashMap::new();
+    let mut edges = Vec::new();
+
+    for mut line in parse::lines(input) {
+        let from = line.int()?;
+        line.literal(" ->")?;
+        let to = line.int()?;
+        line.end()?;
+
+        let mut id = |node| {
+            let next = index.len();
+            *index.entry(node).or_insert(next)
+        };
+        edges.push((id(from), id(to)));
     }
 
-    max_len
+    let mut graph = Graph::new(index.len());
+    for (from, to) in edges {
+        graph.add_edge(from, to);
+    }
+    Ok(graph)
 }
 
-fn longest_path(node: u32, edges: &HashMap<u32, Vec<u32>>, memo: &mut HashMap<u32, u32>) -> u32 {
-    if let Some(&len) = memo.get(&node) {
-        return len;
-    }
+fn part1(graph: &Graph) -> Result<u32, String> {
+    let order = graph::topological_order(graph, 0..graph.len())
+        .map_err(|_| "the network has a cycle: no longest path".to_string())?;
 
-    let mut max_depth = 0;
-    if let Some(neighbors) = edges.get(&node) {
-        for &neighbor in neighbors {
-            max_depth = max_depth.max(longest_path(neighbor, edges, memo));
+    // number of nodes of the longest path starting from each node,
+    // successors coming later in the order
+    let mut longest = vec![1; graph.len()];
+    for &node in order.iter().rev() {
+        for &next in graph.successors(node) {
+            longest[node] = longest[node].max(1 + longest[next]);
         }
     }
 
-    let result = 1 + max_depth; // 1 for current node
-    memo.insert(node, result);
-    result
+    Ok(longest.into_iter().max().unwrap_or(0))
 }
 
-fn part2(input: &str) -> u64 {
-    let edges = parse_input(input);
-    let mut nodes = std::collections::HashSet::new();
-    for (&u, neighbors) in &edges {
-        nodes.insert(u);
-        for &v in neighbors {
-            nodes.insert(v);
-        }
-    }
-
-    let mut ids: HashMap<u32, u32> = HashMap::new();
-    let mut low: HashMap<u32, u32> = HashMap::new();
-    let mut on_stack: HashMap<u32, bool> = HashMap::new();
-    let mut stack: Vec<u32> = Vec::new();
-
-    let mut id_counter = 0;
-    let mut scc_count = 0;
-    let mut node_scc: HashMap<u32, u32> = HashMap::new(); // Map node -> scc_id
-
-    // Sort nodes for deterministic behavior if needed, or just iterate
-    for &node in &nodes {
-        if !ids.contains_key(&node) {
-            dfs(
-                node,
-                &edges,
-                &mut ids,
-                &mut low,
-                &mut on_stack,
-                &mut stack,
-                &mut id_counter,
-                &mut scc_count,
-                &mut node_scc,
-            );
-        }
-    }
+fn part2(graph: &Graph) -> u64 {
+    let (node_scc, scc_count) = graph::strongly_connected_components(graph);
 
     if scc_count == 1 {
         return 0;
@@ -93,14 +63,11 @@ fn part2(input: &str) -> u64 {
     let mut scc_in_degree = vec![0; scc_count];
     let mut scc_out_degree = vec![0; scc_count];
 
-    for (&u, neighbors) in &edges {
-        let u_scc = node_scc[&u] as usize;
-        for &v in neighbors {
-            let v_scc = node_scc[&v] as usize;
-            if u_scc != v_scc {
-                scc_out_degree[u_scc] += 1;
-                scc_in_degree[v_scc] += 1;
-            }
+    for (u, v) in graph.edges() {
+        let (u_scc, v_scc) = (node_scc[u], node_scc[v]);
+        if u_scc != v_scc {
+            scc_out_degree[u_scc] += 1;
+            scc_in_degree[v_scc] += 1;
         }
     }
 
@@ -110,58 +77,8 @@ fn part2(input: &str) -> u64 {
     sources.max(sinks) as u64
 }
 
-#[allow(clippy::too_many_arguments)]
-fn dfs(
-    at: u32,
-    edges: &HashMap<u32, Vec<u32>>,
-    ids: &mut HashMap<u32, u32>,
-    low: &mut HashMap<u32, u32>,
-    on_stack: &mut HashMap<u32, bool>,
-    stack: &mut Vec<u32>,
-    id_counter: &mut u32,
-    scc_count: &mut usize,
-    node_scc: &mut HashMap<u32, u32>,
-) {
-    stack.push(at);
-    on_stack.insert(at, true);
-    ids.insert(at, *id_counter);
-    low.insert(at, *id_counter);
-    *id_counter += 1;
-
-    if let Some(neighbors) = edges.get(&at) {
-        for &to in neighbors {
-            if !ids.contains_key(&to) {
-                dfs(
-                    to, edges, ids, low, on_stack, stack, id_counter, scc_count, node_scc,
-                );
-                let low_at = *low.get(&at).unwrap();
-                let low_to = *low.get(&to).unwrap();
-                low.insert(at, low_at.min(low_to));
-            } else if *on_stack.get(&to).unwrap_or(&false) {
-                let low_at = *low.get(&at).unwrap();
-                let id_to = *ids.get(&to).unwrap();
-                low.insert(at, low_at.min(id_to));
-            }
-        }
-    }
-
-    if ids[&at] == low[&at] {
-        while let Some(node) = stack.pop() {
-            on_stack.insert(node, false);
-            node_scc.insert(node, *scc_count as u32);
-            if node == at {
-                break;
-            }
-        }
-        *scc_count += 1;
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    const TEST_INPUT: &str = "\
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 0 -> 3
 1 -> 2
 2 -> 3
@@ -171,13 +88,84 @@ mod tests {
 4 -> 7
 ";
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
+    fn network(input: &str) -> Graph {
+        parse_input(input).unwrap()
+    }
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 4);
+        assert_eq!(part1(&network(EXAMPLE)), Ok(4));
+        assert!(part1(&network("1 -> 2\n2 -> 1\n")).is_err());
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 3);
+        assert_eq!(part2(&network(EXAMPLE)), 3);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_input("1 -> 2\n\n3 -> 4\n\n").is_ok());
+        assert_eq!(
+            parse_input("1 -> 2\n3 => 4").err(),
+            Some("line 2, column 2: expected \" ->\", found \"=>\"".to_string())
+        );
+        assert!(parse_input("1 -> x").is_err());
+        assert!(parse_input("1 -> 2 -> 3").is_err());
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let n = 1_000_000;
+        let input: String = (0..n).map(|i| format!("{i} -> {}\n", i + 1)).collect();
+        let graph = network(&input);
+        assert_eq!(part1(&graph), Ok(n + 1));
+        assert_eq!(part2(&graph), 1);
+    }
+
+    /// Edges of a DAG: from lower to higher node numbers.
+    fn dag(s: &mut Source) -> Vec<(u32, u32)> {
+        let n = s.int(2u32..=8);
+        s.vec(1..=15, |s| {
+            let from = s.int(0..=n - 2);
+            (from, s.int(from + 1..=n - 1))
+        })
+    }
+
+    #[test]
+    fn test_properties_on_dag() {
+        property::check(dag, |edges| {
+            let input: String = edges.iter().map(|(a, b)| format!("{a} -> {b}\n")).collect();
+
+            let n = 8;
+            let present = |v: u32| edges.iter().any(|&(a, b)| a == v || b == v);
+
+            // longest path, in nodes, by decreasing node number
+            let mut longest = vec![1; n];
+            for v in (0..n).rev() {
+                for &(a, b) in edges {
+                    if a as usize == v {
+                        longest[v] = longest[v].max(1 + longest[b as usize]);
+                    }
+                }
+            }
+
+            // in a DAG, every node is its own strongly connected component
+            let sources = (0..n as u32)
+                .filter(|&v| present(v) && !edges.iter().any(|&(_, b)| b == v))
+                .count();
+            let sinks = (0..n as u32)
+                .filter(|&v| present(v) && !edges.iter().any(|&(a, _)| a == v))
+                .count();
+
+            let graph = network(&input);
+            part1(&graph) == Ok(*longest.iter().max().unwrap())
+                && part2(&graph) == sources.max(sinks) as u64
+        });
     }
 }
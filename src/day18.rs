@@ -1,42 +1,60 @@
 // Day 18: Pattern Matching
 // https://lovemathboy.github.io/day18.html
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day18.txt").ok()?;
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+use crate::parse::{self, Line};
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let (patterns, target) = parse_input(input)?;
+    Ok((
+        part1(&patterns, target).to_string(),
+        part2(&patterns, target).to_string(),
+    ))
 }
 
-fn parse_input(input: &str) -> (Vec<&str>, &str) {
-    let mut lines = input.lines();
-    let mut patterns = Vec::new();
-    let mut target = "";
-
-    // Skip "Pattern:"
-    if let Some(first) = lines.next()
-        && first.trim() != "Pattern:"
-    {
-        // Handle case where "Pattern:" might be missing or different
-        // For now assume standard format, but maybe print warning if not
-    }
-
-    while let Some(line) = lines.next() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-        if line == "String:" {
-            if let Some(t) = lines.next() {
-                target = t.trim();
-            }
-            break;
+/// The whole line, checking that its characters belong to `alphabet`.
+fn symbols<'a>(mut line: Line<'a>, alphabet: &str) -> Result<&'a str, String> {
+    let mut whole = line;
+    while let Some(ch) = line.peek() {
+        if !alphabet.contains(ch) {
+            return Err(line.error(format!("unexpected character {ch:?}")));
         }
-        patterns.push(line);
+        line.char()?;
     }
-    (patterns, target)
+    Ok(whole.rest())
+}
+
+/// The patterns and the target string.
+fn parse_input(input: &str) -> Result<(Vec<&str>, &str), String> {
+    let blocks = parse::blocks(input);
+    let [patterns, string] = &blocks[..] else {
+        return Err(format!(
+            "expected a block of patterns and a block with the string, found {} blocks",
+            blocks.len()
+        ));
+    };
+
+    let mut header = patterns.header();
+    header.literal("Pattern:")?;
+    header.end()?;
+    let patterns = patterns
+        .body()
+        .map(|line| symbols(line, "01?"))
+        .collect::<Result<_, _>>()?;
+
+    let mut header = string.header();
+    header.literal("String:")?;
+    header.end()?;
+    let mut body = string.body();
+    let (Some(target), None) = (body.next(), body.next()) else {
+        return Err(string
+            .header()
+            .error("expected the string on a single line"));
+    };
+
+    Ok((patterns, symbols(target, "01")?))
 }
 
-fn part1(input: &str) -> usize {
-    let (patterns, target) = parse_input(input);
+fn part1(patterns: &[&str], target: &str) -> usize {
     patterns
         .iter()
         .filter(|pattern| matches(pattern, target))
@@ -53,9 +71,7 @@ fn matches(pattern: &str, target: &str) -> bool {
         .all(|(p, t)| p == '?' || p == t)
 }
 
-fn part2(input: &str) -> usize {
-    let (patterns, target) = parse_input(input);
-
+fn part2(patterns: &[&str], target: &str) -> usize {
     let pattern = patterns.join("");
     let n = pattern.len();
     let m = target.len();
@@ -109,11 +125,8 @@ fn part2(input: &str) -> usize {
     dp[0]
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    const TEST_INPUT: &str = "\
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 Pattern:
 101101?
 1??????
@@ -123,13 +136,55 @@ Pattern:
 String:
 1011011";
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 3);
+        let (patterns, target) = parse_input(EXAMPLE).unwrap();
+        assert_eq!(part1(&patterns, target), 3);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 4);
+        let (patterns, target) = parse_input(EXAMPLE).unwrap();
+        assert_eq!(part2(&patterns, target), 4);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input("Pattern:\n1?\n1x\n\nString:\n10").err(),
+            Some("line 3, column 2: unexpected character 'x'".to_string())
+        );
+        assert!(parse_input("Pattern:\n1?\n\nString:\n1?").is_err());
+        assert!(parse_input("Pattern:\n1?\n\nString:\n10\n11").is_err());
+        assert!(parse_input("Patterns:\n1?\n\nString:\n10").is_err());
+        assert!(parse_input("Pattern:\n1?\n").is_err());
+    }
+
+    fn patterns(s: &mut Source) -> (Vec<String>, String) {
+        let m = s.int(1usize..=6);
+        let target = (0..m).map(|_| *s.choose(&['0', '1'])).collect();
+        let patterns = s.vec(1..=6, |s| {
+            (0..m).map(|_| *s.choose(&['?', '0', '1'])).collect()
+        });
+        (patterns, target)
+    }
+
+    #[test]
+    fn test_properties() {
+        property::check(patterns, |(patterns, target)| {
+            let input = format!("Pattern:\n{}\n\nString:\n{target}", patterns.join("\n"));
+            let (patterns, target) = parse_input(&input).unwrap();
+            let (matching, occurrences) = (part1(&patterns, target), part2(&patterns, target));
+
+            // matching patterns of the target length give non-overlapping occurrences
+            matching <= patterns.len()
+                && occurrences >= matching
+                && occurrences <= patterns.len() * target.len() - target.len() + 1
+        });
     }
 }
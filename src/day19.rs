@@ -1,174 +1,135 @@
 // Day 19: Grid Traversal
 // https://lovemathboy.github.io/day19.html
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day19.txt").ok()?;
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+use crate::bigint::BigUint;
+use crate::grid::{Grid, Pos};
+use crate::parse;
+use crate::render::{self, Color, Picture};
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let grids = parse_input(input)?;
+    Ok((part1(&grids).to_string(), part2(&grids).to_string()))
 }
 
-fn parse_input(input: &str) -> Vec<Vec<Vec<u8>>> {
-    input
-        .trim()
-        .split("\n\n")
-        .map(|grid| {
-            grid.lines()
-                .map(|row| row.bytes().map(|c| c - b'0').collect())
-                .collect()
-        })
-        .collect()
-}
+fn parse_input(input: &str) -> Result<Vec<Grid<u8>>, String> {
+    let grids: Vec<_> = parse::blocks(input)
+        .iter()
+        .map(|block| Grid::parse(block.lines(), |c| c.to_digit(10).map(|d| d as u8)))
+        .collect::<Result<_, _>>()?;
 
-fn solve_grid(grid: &[Vec<u8>]) -> u64 {
-    let rows = grid.len();
-    if rows == 0 {
-        return 0;
-    }
-    let cols = grid[0].len();
-    if cols == 0 {
-        return 0;
+    if grids.is_empty() {
+        return Err("no grid".to_string());
     }
+    Ok(grids)
+}
 
-    let mut dp = vec![vec![0; cols]; rows];
-
-    // Start position
-    dp[0][0] = grid[0][0] as u64;
+/// Maximum score of a path from the top-left corner to each cell, moving down or right.
+fn best_paths(grid: &Grid<u8>) -> Grid<u64> {
+    let mut best = Grid::new(grid.rows(), grid.cols(), 0);
 
-    // First row
-    for j in 1..cols {
-        dp[0][j] = dp[0][j - 1] + grid[0][j] as u64;
+    for (r, c) in grid.positions() {
+        let before = match (r, c) {
+            (0, 0) => 0,
+            (0, _) => best[(0, c - 1)],
+            (_, 0) => best[(r - 1, 0)],
+            _ => best[(r - 1, c)].max(best[(r, c - 1)]),
+        };
+        best[(r, c)] = before + u64::from(grid[(r, c)]);
     }
 
-    // First col
-    for i in 1..rows {
-        dp[i][0] = dp[i - 1][0] + grid[i][0] as u64;
-    }
+    best
+}
 
-    // The rest
-    for i in 1..rows {
-        for j in 1..cols {
-            dp[i][j] = grid[i][j] as u64 + dp[i - 1][j].max(dp[i][j - 1]);
-        }
+/// Cells of a path of maximum score from the top-left to the bottom-right corner.
+fn best_path(grid: &Grid<u8>) -> Vec<Pos> {
+    let best = best_paths(grid);
+    let mut pos = (grid.rows() - 1, grid.cols() - 1);
+    let mut path = vec![pos];
+
+    while pos != (0, 0) {
+        let (r, c) = pos;
+        let before = best[pos] - u64::from(grid[pos]);
+        pos = if r > 0 && best[(r - 1, c)] == before {
+            (r - 1, c)
+        } else {
+            (r, c - 1)
+        };
+        path.push(pos);
     }
 
-    dp[rows - 1][cols - 1]
+    path
 }
 
-fn part1(input: &str) -> u64 {
-    let grids = parse_input(input);
-    grids.iter().map(|grid| solve_grid(grid)).product()
+fn solve_grid(grid: &Grid<u8>) -> u64 {
+    best_paths(grid)[(grid.rows() - 1, grid.cols() - 1)]
 }
 
-fn solve_2_robots(grid: &[Vec<u8>]) -> u64 {
-    let rows = grid.len();
-    if rows == 0 {
-        return 0;
-    }
-    let cols = grid[0].len();
-    if cols == 0 {
-        return 0;
-    }
-
-    // Robot 1: TL to BR (Down, Right)
-    // dp1_start[i][j]: max path from (0,0) to (i,j)
-    let mut dp1_start = vec![vec![0; cols]; rows];
-    dp1_start[0][0] = grid[0][0] as u64;
-    for j in 1..cols {
-        dp1_start[0][j] = dp1_start[0][j - 1] + grid[0][j] as u64;
-    }
-    for i in 1..rows {
-        dp1_start[i][0] = dp1_start[i - 1][0] + grid[i][0] as u64;
-    }
-    for i in 1..rows {
-        for j in 1..cols {
-            dp1_start[i][j] =
-                grid[i][j] as u64 + std::cmp::max(dp1_start[i - 1][j], dp1_start[i][j - 1]);
-        }
-    }
-
-    // dp1_end[i][j]: max path from (i,j) to (R-1, C-1)
-    let mut dp1_end = vec![vec![0; cols]; rows];
-    dp1_end[rows - 1][cols - 1] = grid[rows - 1][cols - 1] as u64;
-    for j in (0..cols - 1).rev() {
-        dp1_end[rows - 1][j] = dp1_end[rows - 1][j + 1] + grid[rows - 1][j] as u64;
-    }
-    for i in (0..rows - 1).rev() {
-        dp1_end[i][cols - 1] = dp1_end[i + 1][cols - 1] + grid[i][cols - 1] as u64;
-    }
-    for i in (0..rows - 1).rev() {
-        for j in (0..cols - 1).rev() {
-            dp1_end[i][j] = grid[i][j] as u64 + std::cmp::max(dp1_end[i + 1][j], dp1_end[i][j + 1]);
-        }
-    }
+fn part1(grids: &[Grid<u8>]) -> BigUint {
+    grids
+        .iter()
+        .map(|grid| BigUint::from(solve_grid(grid)))
+        .product()
+}
 
-    // Robot 2: BL to TR (Up, Right)
-    // dp2_start[i][j]: max path from (R-1, 0) to (i,j)
-    let mut dp2_start = vec![vec![0; cols]; rows];
-    dp2_start[rows - 1][0] = grid[rows - 1][0] as u64;
-    for j in 1..cols {
-        dp2_start[rows - 1][j] = dp2_start[rows - 1][j - 1] + grid[rows - 1][j] as u64;
-    }
-    for i in (0..rows - 1).rev() {
-        dp2_start[i][0] = dp2_start[i + 1][0] + grid[i][0] as u64;
-    }
-    for i in (0..rows - 1).rev() {
-        for j in 1..cols {
-            dp2_start[i][j] =
-                grid[i][j] as u64 + std::cmp::max(dp2_start[i + 1][j], dp2_start[i][j - 1]);
-        }
-    }
+fn solve_2_robots(grid: &Grid<u8>) -> u64 {
+    let (rows, cols) = (grid.rows(), grid.cols());
 
-    // dp2_end[i][j]: max path from (i,j) to (0, C-1)
-    let mut dp2_end = vec![vec![0; cols]; rows];
-    dp2_end[0][cols - 1] = grid[0][cols - 1] as u64;
-    for j in (0..cols - 1).rev() {
-        dp2_end[0][j] = dp2_end[0][j + 1] + grid[0][j] as u64;
-    }
-    for i in 1..rows {
-        dp2_end[i][cols - 1] = dp2_end[i - 1][cols - 1] + grid[i][cols - 1] as u64;
-    }
-    for i in 1..rows {
-        for j in (0..cols - 1).rev() {
-            dp2_end[i][j] = grid[i][j] as u64 + std::cmp::max(dp2_end[i - 1][j], dp2_end[i][j + 1]);
-        }
-    }
+    // Robot 1 goes from the top-left to the bottom-right corner (down, right),
+    // robot 2 from the bottom-left to the top-right corner (up, right).
+    // Paths from or to the other corners are the top-left ones of the mirrored grid.
+    let from_top_left = best_paths(grid);
+    let to_bottom_right = best_paths(&grid.flip_horizontal().flip_vertical())
+        .flip_vertical()
+        .flip_horizontal();
+    let from_bottom_left = best_paths(&grid.flip_vertical()).flip_vertical();
+    let to_top_right = best_paths(&grid.flip_horizontal()).flip_horizontal();
 
     let mut max_score = 0;
 
     // Iterate over possible intersection points (not on the border)
     for i in 1..rows - 1 {
         for j in 1..cols - 1 {
-            // Case 1: Robot 1 Vertical (comes from Top, goes Bottom), Robot 2 Horizontal (comes from Left, goes Right)
-            // R1: (i-1, j) -> (i, j) -> (i+1, j)
-            // R2: (i, j-1) -> (i, j) -> (i, j+1)
-            let score1 = dp1_start[i - 1][j] + dp1_end[i + 1][j];
-            let score2 = dp2_start[i][j - 1] + dp2_end[i][j + 1];
             // Add intersection twice (once for each robot)
-            let total1 = score1 + score2 + 2 * grid[i][j] as u64;
-            max_score = std::cmp::max(max_score, total1);
+            let twice = 2 * u64::from(grid[(i, j)]);
+
+            // Case 1: Robot 1 Vertical (comes from Top, goes Bottom), Robot 2 Horizontal (comes from Left, goes Right)
+            let score1 = from_top_left[(i - 1, j)] + to_bottom_right[(i + 1, j)];
+            let score2 = from_bottom_left[(i, j - 1)] + to_top_right[(i, j + 1)];
+            max_score = max_score.max(score1 + score2 + twice);
 
             // Case 2: Robot 1 Horizontal (comes from Left, goes Right), Robot 2 Vertical (comes from Bottom, goes Top)
-            // R1: (i, j-1) -> (i, j) -> (i, j+1)
-            // R2: (i+1, j) -> (i, j) -> (i-1, j)
-            let score3 = dp1_start[i][j - 1] + dp1_end[i][j + 1];
-            let score4 = dp2_start[i + 1][j] + dp2_end[i - 1][j];
-            let total2 = score3 + score4 + 2 * grid[i][j] as u64;
-            max_score = std::cmp::max(max_score, total2);
+            let score3 = from_top_left[(i, j - 1)] + to_bottom_right[(i, j + 1)];
+            let score4 = from_bottom_left[(i + 1, j)] + to_top_right[(i - 1, j)];
+            max_score = max_score.max(score3 + score4 + twice);
         }
     }
 
     max_score
 }
 
-fn part2(input: &str) -> u64 {
-    let grids = parse_input(input);
-    grids.iter().map(|grid| solve_2_robots(grid)).product()
+fn part2(grids: &[Grid<u8>]) -> BigUint {
+    grids
+        .iter()
+        .map(|grid| BigUint::from(solve_2_robots(grid)))
+        .product()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// The grids with the best path of part 1 highlighted.
+pub fn render(input: &str) -> Result<Vec<Picture>, String> {
+    let grids = parse_input(input)?;
+    Ok(grids
+        .iter()
+        .map(|grid| {
+            let path = best_path(grid);
+            Picture::Terminal(render::ansi_grid(grid, |pos| {
+                path.contains(&pos).then_some(Color::Green)
+            }))
+        })
+        .collect())
+}
 
-    const TEST_INPUT: &str = "\
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 19532
 36182
 93847
@@ -179,13 +140,58 @@ mod tests {
 456
 789";
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 1450);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()).to_string(), "1450");
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 4650);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()).to_string(), "4650");
+    }
+
+    #[test]
+    fn test_best_path() {
+        let grids = parse_input(EXAMPLE).unwrap();
+        let path = best_path(&grids[1]);
+        assert_eq!(path, [(2, 2), (2, 1), (2, 0), (1, 0), (0, 0)]);
+        let score: u64 = path.iter().map(|&pos| u64::from(grids[1][pos])).sum();
+        assert_eq!(score, solve_grid(&grids[1]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input("123\n456\n\n12\n3a"),
+            Err("line 5, column 2: unexpected character 'a'".to_string())
+        );
+    }
+
+    fn grid(s: &mut Source) -> Grid<u8> {
+        s.grid(3..=7, 3..=7, |s| s.int(1u8..=9))
+    }
+
+    #[test]
+    fn test_one_robot_bounds() {
+        property::check(grid, |grid| {
+            let (rows, cols) = (grid.rows(), grid.cols());
+            let corners = (grid[(0, 0)] + grid[(rows - 1, cols - 1)]) as u64;
+            let total = grid.iter().map(|(_, &c)| c as u64).sum();
+            (corners..=total).contains(&solve_grid(grid))
+        });
+    }
+
+    #[test]
+    fn test_two_robots_score_twice_interior_max() {
+        property::check(grid, |grid| {
+            let interior = grid.view((1, 1), grid.rows() - 2, grid.cols() - 2);
+            let interior_max = interior.iter().map(|(_, &c)| c).max().unwrap() as u64;
+            solve_2_robots(grid) >= 2 * interior_max
+        });
     }
 }
@@ -3,10 +3,15 @@
 
 use std::collections::HashSet;
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day20.txt").ok()?;
+use crate::parse::{self, Line};
+use crate::render::{Color, Picture, Svg};
 
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let sprinklers = parse_input(input)?;
+    Ok((
+        part1(&sprinklers).to_string(),
+        part2(&sprinklers).to_string(),
+    ))
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -51,26 +56,45 @@ impl Circle {
     }
 }
 
-fn parse_input(input: &str) -> Vec<Circle> {
-    input
-        .trim()
-        .lines()
-        .map(|line| {
-            // Parse lines like "(4, 6) r=3"
-            let (xy, r) = line.split_once(") r=").unwrap();
-            let (x, y) = xy.trim_start_matches('(').split_once(", ").unwrap();
-            Circle {
-                x: x.parse().unwrap(),
-                y: y.parse().unwrap(),
-                r: r.parse().unwrap(),
+/// Largest coordinate or radius accepted: squared distances must fit in an i64.
+const MAX_COORDINATE: i64 = 1 << 30;
+
+fn coordinate(line: &mut Line) -> Result<i64, String> {
+    let at = *line;
+    let value: i64 = line.int()?;
+    if value.abs() > MAX_COORDINATE {
+        return Err(at.error(format!("{value} is out of range")));
+    }
+    Ok(value)
+}
+
+/// "(4, 6) r=3"
+fn parse_input(input: &str) -> Result<Vec<Circle>, String> {
+    let sprinklers: Vec<Circle> = parse::lines(input)
+        .map(|mut line| {
+            line.literal("(")?;
+            let x = coordinate(&mut line)?;
+            line.literal(",")?;
+            let y = coordinate(&mut line)?;
+            line.literal(") r=")?;
+            let at = line;
+            let r = coordinate(&mut line)?;
+            if r < 0 {
+                return Err(at.error("negative radius"));
             }
+            line.end()?;
+            Ok(Circle { x, y, r })
         })
-        .collect()
-}
+        .collect::<Result<_, String>>()?;
 
-fn part1(_input: &str) -> i64 {
-    let sprinklers = parse_input(_input);
+    if sprinklers.is_empty() {
+        return Err("no sprinkler".to_string());
+    }
+    Ok(sprinklers)
+}
 
+/// Index of the sprinkler overlapping the most others, and their number.
+fn most_overlapping(sprinklers: &[Circle]) -> (usize, i64) {
     let mut overlaps = vec![0; sprinklers.len()];
 
     for (idx, sprinkler) in sprinklers.iter().enumerate() {
@@ -81,22 +105,25 @@ fn part1(_input: &str) -> i64 {
             }
         }
     }
-    let max = overlaps
+    overlaps
         .into_iter()
         .enumerate()
         .max_by_key(|(_, v)| *v)
-        .unwrap();
+        .unwrap()
+}
 
-    let max_sprinkler = &sprinklers[max.0];
-    max_sprinkler.x * max_sprinkler.y + max.1
+fn part1(sprinklers: &[Circle]) -> i64 {
+    let (max, count) = most_overlapping(sprinklers);
+    let max_sprinkler = &sprinklers[max];
+    max_sprinkler.x * max_sprinkler.y + count
 }
 
-fn part2(input: &str) -> i64 {
-    let sprinklers = parse_input(input);
+/// Point covered by the most sprinklers.
+fn best_point(sprinklers: &[Circle]) -> (i64, i64) {
     let mut candidates = HashSet::new();
 
     // Add centers
-    for c in &sprinklers {
+    for c in sprinklers {
         candidates.insert((c.x, c.y));
     }
 
@@ -122,7 +149,7 @@ fn part2(input: &str) -> i64 {
 
     for (cx, cy) in candidates {
         let mut count = 0;
-        for s in &sprinklers {
+        for s in sprinklers {
             if s.contains(cx, cy) {
                 count += 1;
             }
@@ -134,27 +161,97 @@ fn part2(input: &str) -> i64 {
         }
     }
 
-    best_coord.0 * best_coord.1
+    best_coord
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn part2(sprinklers: &[Circle]) -> i64 {
+    let (x, y) = best_point(sprinklers);
+    x * y
+}
+
+/// The sprinklers, the one of part 1 in red and the point of part 2 in green.
+pub fn render(input: &str) -> Result<Vec<Picture>, String> {
+    let sprinklers = parse_input(input)?;
+
+    let bound = |f: fn(&Circle) -> i64| sprinklers.iter().map(f).map(|v| v as f64);
+    let top_left = (
+        bound(|c| c.x - c.r).fold(f64::INFINITY, f64::min),
+        bound(|c| c.y - c.r).fold(f64::INFINITY, f64::min),
+    );
+    let bottom_right = (
+        bound(|c| c.x + c.r).fold(f64::NEG_INFINITY, f64::max),
+        bound(|c| c.y + c.r).fold(f64::NEG_INFINITY, f64::max),
+    );
+    let mut svg = Svg::new(top_left, bottom_right);
+
+    let (max, count) = most_overlapping(&sprinklers);
+    for (i, c) in sprinklers.iter().enumerate() {
+        let color = if i == max { Color::Red } else { Color::Blue };
+        svg.circle((c.x as f64, c.y as f64), c.r as f64, color);
+    }
 
-    const TEST_INPUT: &str = "\
+    let (x, y) = best_point(&sprinklers);
+    svg.dot((x as f64, y as f64), Color::Green);
+    svg.label(
+        top_left,
+        &format!("red: {count} overlaps, green: ({x}, {y})"),
+    );
+
+    Ok(vec![Picture::Svg(svg)])
+}
+
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 (4, 6) r=3
 (3, 7) r=1
 (12, 14) r=9
 (10, 6) r=5
 ";
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 27);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), 27);
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 48);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 48);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input("(1, 2) r=3\n(1; 2) r=3").err(),
+            Some("line 2, column 3: expected \",\", found \";\"".to_string())
+        );
+        assert!(parse_input("(1, 2) r=-3").is_err());
+        assert!(parse_input("(1, 99999999999) r=3").is_err());
+        assert!(parse_input("(1, 2)").is_err());
+        assert!(parse_input("\n").is_err());
+    }
+
+    fn circle(s: &mut Source) -> Circle {
+        Circle {
+            x: s.int(-20i64..=20),
+            y: s.int(-20i64..=20),
+            r: s.int(1i64..=10),
+        }
+    }
+
+    #[test]
+    fn test_circle_properties() {
+        property::check(
+            |s| (circle(s), circle(s)),
+            |(a, b)| {
+                a.contains(a.x, a.y)
+                    && a.overlaps(b) == b.overlaps(a)
+                    && (!a.contains(b.x, b.y) || a.overlaps(b))
+            },
+        );
     }
 }
@@ -4,55 +4,97 @@
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day21.txt").ok()?;
-    let part1_res = part1(&input);
-    let part2_res = part2(&input);
+use crate::bigint::BigUint;
+use crate::graph;
+use crate::grid::{Grid, View};
+use crate::inspect::{self, Report};
+use crate::parse;
+use crate::render::{self, Color, Picture};
+use crate::trace;
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let part1_res = part1(input)?;
+    let part2_res = part2(input)?;
+
+    Ok((part1_res.to_string(), part2_res.to_string()))
+}
+
+/// Sizes and markers of the faces, and whether part 2 can fold them into a cube.
+pub fn inspect(input: &str, report: &mut Report) -> Result<(), String> {
+    let grids = parse_input(input)?;
+    let markers: Vec<usize> = grids
+        .iter()
+        .map(|grid| grid.iter().filter(|&(_, &c)| c == 'O').count())
+        .collect();
+
+    let mut sizes: Vec<String> = grids
+        .iter()
+        .map(|grid| format!("{}x{}", grid.rows(), grid.cols()))
+        .collect();
+    sizes.sort();
+    sizes.dedup();
+    report.stat("faces", grids.len());
+    report.stat("face sizes", sizes.join(", "));
+    report.stat(
+        "O markers per face",
+        inspect::range(markers.iter().copied()),
+    );
+
+    for (i, &count) in (1..).zip(&markers) {
+        if count != 2 {
+            report.violation(format!(
+                "face {i} has {count} O markers: part 1 joins exactly 2 per face"
+            ));
+        }
+    }
 
-    Some((part1_res.to_string(), part2_res.to_string()))
+    let n = grids[0].rows();
+    if grids.len() != 6 {
+        report.violation(format!(
+            "part 2 folds 6 faces into a cube, found {}",
+            grids.len()
+        ));
+    } else if grids
+        .iter()
+        .any(|grid| (grid.rows(), grid.cols()) != (n, n))
+    {
+        report.violation("part 2 needs square faces all of the same size");
+    } else if find_layout(&grids).is_none() {
+        report.violation("the edges of the faces do not match when folded: part 2 gives 0");
+    }
+    Ok(())
 }
 
-type Grid = Vec<Vec<char>>;
-
-fn parse_input(input: &str) -> Vec<Grid> {
-    input
-        .trim()
-        .split("\n\n")
-        .map(|grid_str| {
-            grid_str
-                .lines()
-                .map(|line| line.chars().collect())
-                .collect()
-        })
-        .collect()
+fn parse_input(input: &str) -> Result<Vec<Grid<char>>, String> {
+    let grids: Vec<_> = parse::blocks(input)
+        .iter()
+        .map(|block| Grid::parse(block.lines(), |c| matches!(c, '#' | '.' | 'O').then_some(c)))
+        .collect::<Result<_, _>>()?;
+
+    if grids.is_empty() {
+        return Err("no grid".to_string());
+    }
+    Ok(grids)
 }
 
 // -----------------------------------------------------------------------------
-// 1. Grid Parsing and Boundary Extraction
+// 1. Grid Boundaries
 // -----------------------------------------------------------------------------
 
-fn get_boundary(grid: &Grid, side: usize) -> Vec<bool> {
-    let rows = grid.len();
-    let cols = grid[0].len();
+/// Cells along a side of the grid: 0 top, 1 right, 2 bottom, 3 left.
+/// They are ordered from left to right or from top to bottom.
+fn edge(grid: &Grid<char>, side: usize) -> View<'_, char> {
+    let (rows, cols) = (grid.rows(), grid.cols());
     match side {
-        0 => (0..cols).map(|c| grid[0][c] != '#').collect(), // Top
-        1 => (0..rows).map(|r| grid[r][cols - 1] != '#').collect(), // Right
-        2 => (0..cols).map(|c| grid[rows - 1][c] != '#').collect(), // Bottom
-        3 => (0..rows).map(|r| grid[r][0] != '#').collect(), // Left
-        _ => vec![],
+        0 => grid.view((0, 0), 1, cols),
+        1 => grid.view((0, cols - 1), rows, 1),
+        2 => grid.view((rows - 1, 0), 1, cols),
+        _ => grid.view((0, 0), rows, 1),
     }
 }
 
-fn rotate_grid(grid: &Grid) -> Grid {
-    let rows = grid.len();
-    let cols = grid[0].len();
-    let mut new_grid = vec![vec![' '; rows]; cols];
-    for r in 0..rows {
-        for c in 0..cols {
-            new_grid[c][rows - 1 - r] = grid[r][c];
-        }
-    }
-    new_grid
+fn get_boundary(grid: &Grid<char>, side: usize) -> Vec<bool> {
+    edge(grid, side).iter().map(|(_, &c)| c != '#').collect()
 }
 
 // -----------------------------------------------------------------------------
@@ -103,7 +145,7 @@ fn check_assignments(assignments: &[(usize, usize)], grid_info: &[Vec<RotatedGri
     true
 }
 
-fn find_layout(grids: &[Grid]) -> Option<Vec<(usize, usize)>> {
+fn find_layout(grids: &[Grid<char>]) -> Option<Vec<(usize, usize)>> {
     let mut grid_info = Vec::new();
     for grid in grids {
         let mut rots = Vec::new();
@@ -111,7 +153,7 @@ fn find_layout(grids: &[Grid]) -> Option<Vec<(usize, usize)>> {
         for _ in 0..4 {
             let sigs: Vec<Vec<bool>> = (0..4).map(|s| get_boundary(&curr, s)).collect();
             rots.push(RotatedGridInfo { sigs });
-            curr = rotate_grid(&curr);
+            curr = curr.rotate();
         }
         grid_info.push(rots);
     }
@@ -227,43 +269,39 @@ impl Graph {
     }
 }
 
-fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
+/// The grids turned as the faces of the layout.
+fn rotated(assignments: &[(usize, usize)], grids: &[Grid<char>]) -> Vec<Grid<char>> {
     let mut rotated_grids = Vec::new();
     for &(g_idx, r_idx) in assignments {
         let mut curr = grids[g_idx].clone();
         for _ in 0..r_idx {
-            curr = rotate_grid(&curr);
+            curr = curr.rotate();
         }
         rotated_grids.push(curr);
     }
+    rotated_grids
+}
+
+/// Cost of the network joining the O markers, and the cells it goes through.
+fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid<char>]) -> (usize, HashSet<NodeId>) {
+    let rotated_grids = rotated(assignments, grids);
 
     let mut g = Graph::new();
     let mut terminals = Vec::new();
 
     for (f_idx, grid) in rotated_grids.iter().enumerate() {
-        let rows = grid.len();
-        let cols = grid[0].len();
-        for r in 0..rows {
-            for c in 0..cols {
-                let char = grid[r][c];
-                if char != '#' {
-                    let u = NodeId(f_idx, r, c);
-                    let weight = if char == '.' { 1 } else { 0 };
-                    g.add_node(u, weight);
-                    if char == 'O' {
-                        terminals.push(u);
-                    }
+        for ((r, c), &char) in grid.iter() {
+            if char != '#' {
+                let u = NodeId(f_idx, r, c);
+                let weight = if char == '.' { 1 } else { 0 };
+                g.add_node(u, weight);
+                if char == 'O' {
+                    terminals.push(u);
+                }
 
-                    for (dr, dc) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
-                        let nr = r as i32 + dr;
-                        let nc = c as i32 + dc;
-                        if nr >= 0 && nr < rows as i32 && nc >= 0 && nc < cols as i32 {
-                            let nr = nr as usize;
-                            let nc = nc as usize;
-                            if grid[nr][nc] != '#' {
-                                g.add_edge(u, NodeId(f_idx, nr, nc), 0);
-                            }
-                        }
+                for (nr, nc) in grid.neighbours4((r, c)) {
+                    if grid[(nr, nc)] != '#' {
+                        g.add_edge(u, NodeId(f_idx, nr, nc), 0);
                     }
                 }
             }
@@ -273,30 +311,16 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
     for &(f1, e1, f2, e2, rev) in &CONNECTIONS {
         let grid1 = &rotated_grids[f1];
         let grid2 = &rotated_grids[f2];
-        let rows1 = grid1.len();
-        let cols1 = grid1[0].len();
-        let rows2 = grid2.len();
-        let cols2 = grid2[0].len();
-
-        let get_coords = |side: usize, r: usize, c: usize| -> Vec<(usize, usize)> {
-            match side {
-                0 => (0..c).map(|x| (0, x)).collect(),
-                1 => (0..r).map(|x| (x, c - 1)).collect(),
-                2 => (0..c).map(|x| (r - 1, x)).collect(),
-                3 => (0..r).map(|x| (x, 0)).collect(),
-                _ => vec![],
-            }
-        };
 
-        let coords1 = get_coords(e1, rows1, cols1);
-        let mut coords2 = get_coords(e2, rows2, cols2);
+        let coords1 = edge(grid1, e1).positions();
+        let mut coords2: Vec<_> = edge(grid2, e2).positions().collect();
 
         if rev {
             coords2.reverse();
         }
 
-        for ((r1_c, c1_c), (r2_c, c2_c)) in coords1.into_iter().zip(coords2.into_iter()) {
-            if grid1[r1_c][c1_c] != '#' && grid2[r2_c][c2_c] != '#' {
+        for ((r1_c, c1_c), (r2_c, c2_c)) in coords1.zip(coords2) {
+            if grid1[(r1_c, c1_c)] != '#' && grid2[(r2_c, c2_c)] != '#' {
                 let u = NodeId(f1, r1_c, c1_c);
                 let v = NodeId(f2, r2_c, c2_c);
                 g.add_edge(u, v, 0);
@@ -316,6 +340,8 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
 
     let mut cg_adj: HashMap<NodeId, Vec<(NodeId, usize)>> = HashMap::new();
     let mut cg_node_weights = HashMap::new();
+    // cheapest chain between two key nodes (the smaller first): weight and inner cells
+    let mut chains: HashMap<(NodeId, NodeId), (usize, Vec<NodeId>)> = HashMap::new();
 
     for &k in &key_nodes {
         if let Some(&w) = g.node_weights.get(&k) {
@@ -329,12 +355,14 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
                 let mut prev = start_node;
                 let mut curr = first_neighbor;
                 let mut path_weight = 0;
+                let mut path = Vec::new();
 
                 loop {
                     if key_nodes.contains(&curr) {
                         break;
                     }
                     path_weight += g.node_weights.get(&curr).cloned().unwrap_or(0);
+                    path.push(curr);
 
                     let curr_neighbors = &g.adj[&curr];
                     if curr_neighbors.is_empty() {
@@ -363,6 +391,10 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
                         .entry(start_node)
                         .or_default()
                         .push((curr, path_weight));
+                    let key = (start_node.min(curr), start_node.max(curr));
+                    if chains.get(&key).is_none_or(|&(w, _)| path_weight < w) {
+                        chains.insert(key, (path_weight, path));
+                    }
                 }
             }
         }
@@ -383,11 +415,9 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
         let mut visited = HashSet::new();
         let mut has_cycle = false;
         for &k in &key_nodes {
-            if !visited.contains(&k) {
-                if has_cycle_dfs(k, None, &cg_adj, &mut visited) {
-                    has_cycle = true;
-                    break;
-                }
+            if !visited.contains(&k) && has_cycle_dfs(k, None, &cg_adj, &mut visited) {
+                has_cycle = true;
+                break;
             }
         }
         !has_cycle
@@ -429,16 +459,18 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
         for &n in &current_nodes {
             total_cost += cg_node_weights.get(&n).copied().unwrap_or(0);
         }
+        let mut cells = current_nodes.clone();
         for &u in &current_nodes {
             if let Some(edges) = cg_adj.get(&u) {
                 for &(v, w) in edges {
                     if current_nodes.contains(&v) && u < v {
                         total_cost += w;
+                        cells.extend(&chains[&(u, v)].1);
                     }
                 }
             }
         }
-        total_cost
+        (total_cost, cells)
     } else {
         // MST Approximation
         let approx_terminals: Vec<NodeId> = terminals
@@ -453,47 +485,31 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
         let mut t_dists: HashMap<NodeId, HashMap<NodeId, usize>> = HashMap::new();
 
         for &start_node in &approx_terminals {
-            let mut dists = HashMap::new();
-            let mut pq = BinaryHeap::new();
-
+            // the weight of a path includes its nodes, the start one included
             let start_weight = cg_node_weights[&start_node];
-            dists.insert(start_node, start_weight);
-            pq.push(State {
-                cost: start_weight,
-                node: start_node,
-            });
-
-            while let Some(State { cost, node: u }) = pq.pop() {
-                if cost > *dists.get(&u).unwrap_or(&usize::MAX) {
-                    continue;
-                }
-
-                if let Some(neighbors) = cg_adj.get(&u) {
-                    for &(v, edge_w) in neighbors {
-                        let node_w = cg_node_weights[&v];
-                        let new_cost = cost + edge_w + node_w;
-                        if new_cost < *dists.get(&v).unwrap_or(&usize::MAX) {
-                            dists.insert(v, new_cost);
-                            pq.push(State {
-                                cost: new_cost,
-                                node: v,
-                            });
-                        }
-                    }
-                }
-            }
+            let dists = graph::dijkstra(start_node, |u| {
+                cg_adj
+                    .get(u)
+                    .into_iter()
+                    .flatten()
+                    .map(|&(v, edge_w)| (v, (edge_w + cg_node_weights[&v]) as u64))
+            })
+            .into_iter()
+            .map(|(v, d)| (v, d as usize + start_weight))
+            .collect();
 
             t_dists.insert(start_node, dists);
         }
 
         if approx_terminals.is_empty() {
-            return 0;
+            return (0, HashSet::new());
         }
 
         let start = approx_terminals[0];
         let mut visited = HashSet::new();
         let mut pq = BinaryHeap::new();
         let mut mst_weight = 0; // Sum of MST edge weights
+        let mut cells = HashSet::new();
 
         // Prim's on the Complete Graph of Terminals
         // "Edge Power" between u and v = distance(u,v) = t_dists[u][v]
@@ -507,12 +523,13 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
                 pq.push(EdgeState {
                     weight: *d,
                     node: target,
+                    from: start,
                 });
             }
         }
 
         while visited.len() < approx_terminals.len() {
-            if let Some(EdgeState { weight, node }) = pq.pop() {
+            if let Some(EdgeState { weight, node, from }) = pq.pop() {
                 if visited.contains(&node) {
                     continue;
                 }
@@ -520,22 +537,46 @@ fn solve_steiner(assignments: &[(usize, usize)], grids: &[Grid]) -> usize {
                 visited.insert(node);
                 mst_weight += weight;
 
-                for &next_target in &approx_terminals {
-                    if !visited.contains(&next_target) {
-                        if let Some(d) = t_dists.get(&node).and_then(|m| m.get(&next_target)) {
-                            pq.push(EdgeState {
-                                weight: *d,
-                                node: next_target,
-                            });
+                // a shortest path from `from`, along the edges matching the distances
+                let dists = &t_dists[&from];
+                let mut parents = HashMap::from([(from, from)]);
+                let mut queue = VecDeque::from([from]);
+                while let Some(u) = queue.pop_front() {
+                    for &(v, w) in cg_adj.get(&u).into_iter().flatten() {
+                        if dists[&u] + w + cg_node_weights[&v] == dists[&v]
+                            && !parents.contains_key(&v)
+                        {
+                            parents.insert(v, u);
+                            queue.push_back(v);
                         }
                     }
                 }
+                let mut curr = node;
+                cells.insert(curr);
+                while curr != from {
+                    let prev = parents[&curr];
+                    cells.extend(&chains[&(prev.min(curr), prev.max(curr))].1);
+                    cells.insert(prev);
+                    curr = prev;
+                }
+
+                for &next_target in &approx_terminals {
+                    if !visited.contains(&next_target)
+                        && let Some(d) = t_dists.get(&node).and_then(|m| m.get(&next_target))
+                    {
+                        pq.push(EdgeState {
+                            weight: *d,
+                            node: next_target,
+                            from: node,
+                        });
+                    }
+                }
             } else {
                 break;
             }
         }
 
-        mst_weight
+        (mst_weight, cells)
     }
 }
 
@@ -554,38 +595,19 @@ fn has_cycle_dfs(
             if visited.contains(&v) {
                 return true;
             }
-            if !visited.contains(&v) {
-                if has_cycle_dfs(v, Some(u), adj, visited) {
-                    return true;
-                }
+            if has_cycle_dfs(v, Some(u), adj, visited) {
+                return true;
             }
         }
     }
     false
 }
 
-#[derive(Eq, PartialEq)]
-struct State {
-    cost: usize,
-    node: NodeId,
-}
-
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.cost.cmp(&self.cost)
-    }
-}
-
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 #[derive(Eq, PartialEq)]
 struct EdgeState {
     weight: usize,
     node: NodeId,
+    from: NodeId,
 }
 
 impl Ord for EdgeState {
@@ -604,92 +626,85 @@ impl PartialOrd for EdgeState {
 // Part 1
 // -----------------------------------------------------------------------------
 
-fn solve_grid(grid: &[Vec<char>]) -> i64 {
-    let mut starts = vec![];
-    for (r, row) in grid.iter().enumerate() {
-        for (c, &ch) in row.iter().enumerate() {
-            if ch == 'O' {
-                starts.push((r, c));
-            }
-        }
-    }
+fn solve_grid(grid: &Grid<char>) -> u64 {
+    let mut starts = grid
+        .iter()
+        .filter(|&(_, &ch)| ch == 'O')
+        .map(|(pos, _)| pos);
 
-    if starts.len() < 2 {
+    let (Some(start), Some(end)) = (starts.next(), starts.next()) else {
         return 0; // Should not happen based on problem description
-    }
-
-    let start = starts[0];
-    let end = starts[1];
-
-    // Dijkstra/BFS
-    let mut dist = vec![vec![i64::MAX; grid[0].len()]; grid.len()];
-    let mut pq = VecDeque::new(); // Using deque for 0-1 BFS
-
-    dist[start.0][start.1] = 0;
-    pq.push_front((0, start.0, start.1));
-
-    while let Some((d, r, c)) = pq.pop_front() {
-        if d > dist[r][c] {
-            continue;
-        }
-        if (r, c) == end {
-            return d;
-        }
+    };
 
-        for (dr, dc) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-            let nr = r as i32 + dr;
-            let nc = c as i32 + dc;
-
-            if nr >= 0 && nr < grid.len() as i32 && nc >= 0 && nc < grid[0].len() as i32 {
-                let nr = nr as usize;
-                let nc = nc as usize;
-                let cell = grid[nr][nc];
-
-                if cell != '#' {
-                    let weight = if cell == '.' { 1 } else { 0 };
-                    if dist[r][c] + weight < dist[nr][nc] {
-                        dist[nr][nc] = dist[r][c] + weight;
-                        if weight == 0 {
-                            pq.push_front((dist[nr][nc], nr, nc));
-                        } else {
-                            pq.push_back((dist[nr][nc], nr, nc));
-                        }
-                    }
-                }
-            }
-        }
-    }
+    // walls are impassable, entering an open cell costs 1 and an O is free
+    let dist = graph::bfs01(start, |&pos| {
+        grid.neighbours4(pos)
+            .filter(|&next| grid[next] != '#')
+            .map(|next| (next, u64::from(grid[next] == '.')))
+    });
 
-    0
+    dist.get(&end).copied().unwrap_or(0)
 }
 
-fn part1(input: &str) -> i64 {
-    let grids = parse_input(input);
-    let mut product = 1;
+fn part1(input: &str) -> Result<BigUint, String> {
+    let grids = parse_input(input)?;
+    Ok(grids
+        .iter()
+        .map(|grid| BigUint::from(solve_grid(grid)))
+        .product())
+}
 
-    for grid in grids {
-        product *= solve_grid(&grid);
+fn part2(input: &str) -> Result<usize, String> {
+    let grids = parse_input(input)?;
+    if grids.len() != 6 {
+        return Err(format!("a cube needs 6 faces, found {}", grids.len()));
     }
+    let Some(assignments) = find_layout(&grids) else {
+        trace::warning!("the faces do not fold into a cube");
+        return Ok(0);
+    };
 
-    product
+    for (face, &(grid, turns)) in assignments.iter().enumerate() {
+        trace::info!(
+            "face {face}: grid {} turned {turns} quarter(s) clockwise",
+            grid + 1
+        );
+    }
+    let (length, _) = solve_steiner(&assignments, &grids);
+    trace::info!("the cheapest network joining the O markers costs {length}");
+    Ok(length)
 }
 
-fn part2(input: &str) -> usize {
-    let grids = parse_input(input);
-    if let Some(assignments) = find_layout(&grids) {
-        solve_steiner(&assignments, &grids)
-    } else {
-        0
+/// The faces as folded by part 2, with the network joining the O markers highlighted.
+pub fn render(input: &str) -> Result<Vec<Picture>, String> {
+    let grids = parse_input(input)?;
+    if grids.len() != 6 {
+        return Err(format!("a cube needs 6 faces, found {}", grids.len()));
     }
-}
+    let Some(assignments) = find_layout(&grids) else {
+        return Err("the faces do not fold into a cube".to_string());
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    let (_, network) = solve_steiner(&assignments, &grids);
+    Ok(rotated(&assignments, &grids)
+        .iter()
+        .enumerate()
+        .map(|(face, grid)| {
+            Picture::Terminal(render::ansi_grid(grid, |(row, col)| {
+                if grid[(row, col)] == 'O' {
+                    Some(Color::Red)
+                } else {
+                    network
+                        .contains(&NodeId(face, row, col))
+                        .then_some(Color::Green)
+                }
+            }))
+        })
+        .collect())
+}
 
-    #[test]
-    fn test_part1() {
-        const TEST_INPUT: &str = "\
+/// Example of the puzzle statement for part 1.
+pub const EXAMPLE: &str = "\
 #####
 #O#.#
 #...O
@@ -702,7 +717,14 @@ mod tests {
 #...#
 ##.##";
 
-        assert_eq!(part1(TEST_INPUT), 18);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(EXAMPLE).map(|n| n.to_string()), Ok("18".to_string()));
     }
 
     #[test]
@@ -744,6 +766,106 @@ mod tests {
 #####
 #####
 ";
-        // assert_eq!(part2(TEST_INPUT), 10);
+        // the faces of the example do not fold into a cube with walls
+        assert_eq!(part2(TEST_INPUT), Ok(0));
+    }
+
+    #[test]
+    fn test_explain() {
+        let wall = "\n\n#####\n#####\n#####\n#####\n#####";
+        let input = format!("{EXAMPLE}{}", wall.repeat(4));
+        let messages = trace::capture(trace::Level::Info, || {
+            assert_eq!(part2(&input), Ok(0));
+        });
+        assert_eq!(messages, ["warning: the faces do not fold into a cube"]);
+    }
+
+    #[test]
+    fn test_inspect() {
+        let mut report = Report::new(EXAMPLE);
+        assert_eq!(inspect(EXAMPLE, &mut report), Ok(()));
+        let text = report.to_string();
+        assert!(text.contains("  face sizes          5x5\n"), "{text}");
+        assert!(text.contains("  O markers per face  2\n"), "{text}");
+        assert!(
+            text.ends_with("  ❌ part 2 folds 6 faces into a cube, found 2\n"),
+            "{text}"
+        );
+
+        let input = "#O#\n#O#\n\n#O#\n#.#\n";
+        let mut report = Report::new(input);
+        assert_eq!(inspect(input, &mut report), Ok(()));
+        let text = report.to_string();
+        assert!(text.contains("  face sizes          2x3\n"), "{text}");
+        assert!(text.contains("  ❌ face 2 has 1 O markers: part 1 joins exactly 2 per face\n"));
+    }
+
+    #[test]
+    fn test_render() {
+        let face = "#.#\n.O.\n#.#\n";
+        let cube = [face; 6].join("\n");
+        let Ok(pictures) = render(&cube) else {
+            panic!("the faces fold into a cube");
+        };
+        assert_eq!(pictures.len(), 6);
+        assert_eq!(part2(&cube), Ok(10));
+        // the network crosses 5 edges, each through an open cell on either side
+        let highlighted: usize = pictures
+            .iter()
+            .map(|picture| match picture {
+                Picture::Terminal(text) => text.matches("\x1b[1;32m").count(),
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(highlighted, 10);
+        assert!(render(EXAMPLE).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_input("###\n#.\n###").is_err());
+        assert!(parse_input("###\n#x#\n###").is_err());
+    }
+
+    fn grid(s: &mut Source) -> Grid<char> {
+        s.grid(2..=6, 2..=6, |s| *s.choose(&['.', '#', 'O']))
+    }
+
+    /// Distance between the two first `O` by relaxing every cell until nothing changes.
+    fn relaxation(grid: &Grid<char>) -> i64 {
+        let cells: Vec<(usize, usize)> = grid.positions().collect();
+        let os: Vec<_> = cells.iter().filter(|&&pos| grid[pos] == 'O').collect();
+        if os.len() < 2 {
+            return 0;
+        }
+
+        let mut dist = Grid::new(grid.rows(), grid.cols(), i64::MAX);
+        dist[*os[0]] = 0;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &(r, c) in &cells {
+                if grid[(r, c)] == '#' || dist[(r, c)] == i64::MAX {
+                    continue;
+                }
+                for &(nr, nc) in &cells {
+                    if grid[(nr, nc)] != '#' && r.abs_diff(nr) + c.abs_diff(nc) == 1 {
+                        let d = dist[(r, c)] + i64::from(grid[(nr, nc)] == '.');
+                        if d < dist[(nr, nc)] {
+                            dist[(nr, nc)] = d;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        let end = dist[*os[1]];
+        if end == i64::MAX { 0 } else { end }
+    }
+
+    #[test]
+    fn test_solve_grid_matches_relaxation() {
+        property::check(grid, |grid| solve_grid(grid) as i64 == relaxation(grid));
     }
 }
@@ -1,47 +1,60 @@
 // Day 22: Passwords
 // https://lovemathboy.github.io/day22.html
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day22.txt").ok()?;
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+use crate::modint::Modulus;
+use crate::parse::{self, Line};
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let users = parse_input(input)?;
+    Ok((part1(&users)?.to_string(), part2(&users).to_string()))
 }
 
-struct User {
+#[derive(Debug)]
+struct User<'a> {
+    /// header of the block, where the errors found when solving are reported
+    at: Line<'a>,
     digit: u32,
     number: u32,
 }
 
-fn parse_input(input: &str) -> Vec<User> {
-    input
-        .split("\n\n")
-        .filter(|s| !s.trim().is_empty())
+/// Largest favorite number accepted: the solvers keep a table of its remainders.
+const MAX_NUMBER: u32 = 1_000_000;
+
+fn parse_input(input: &str) -> Result<Vec<User<'_>>, String> {
+    parse::blocks(input)
+        .iter()
         .map(|block| {
-            let mut lines = block.lines();
-            let _user_line = lines.next().unwrap();
-            let digit_line = lines.next().unwrap();
-            let number_line = lines.next().unwrap();
-
-            let digit = digit_line
-                .trim()
-                .strip_prefix("Favorite Digit: ")
-                .unwrap()
-                .parse()
-                .unwrap();
-
-            let number = number_line
-                .trim()
-                .strip_prefix("Favorite Number: ")
-                .unwrap()
-                .parse()
-                .unwrap();
-
-            User { digit, number }
+            let at = block.header();
+            let mut header = at;
+            header.literal("User ")?;
+            header.int::<u32>()?;
+            header.literal(":")?;
+            header.end()?;
+
+            let mut line = block.field("Favorite Digit")?;
+            let at = line;
+            let digit = line.int()?;
+            if digit > 9 {
+                return Err(at.error(format!("{digit} is not a digit")));
+            }
+            line.end()?;
+
+            let mut line = block.field("Favorite Number")?;
+            let at = line;
+            let number = line.int()?;
+            if !(1..=MAX_NUMBER).contains(&number) {
+                return Err(at.error(format!("{number} is not between 1 and {MAX_NUMBER}")));
+            }
+            line.end()?;
+
+            Ok(User { at, digit, number })
         })
         .collect()
 }
 
 fn solve_user(user: &User) -> String {
     let m = user.number as usize;
+    let modulus = Modulus::new(m as u128);
     let fav = user.digit;
 
     let mut suffixes: Vec<Vec<i32>> = Vec::new();
@@ -85,16 +98,15 @@ fn solve_user(user: &User) -> String {
         let p10_top = ten_pow[len - 1];
         for d1 in 1..10 {
             // Leading digit 1-9
-            let term = (d1 as usize * p10_top) % m;
+            let term = modulus.mul(u128::from(d1), p10_top as u128);
             // We need suffix remainder `needed` such that (term + needed) % m == 0
-            // needed = (m - term) % m
-            let needed = (m - term) % m;
+            let needed = modulus.sub(0, term) as usize;
             let suffix_max_fav = suffixes[len - 1][needed];
             if suffix_max_fav != -1 {
                 let total_fav = suffix_max_fav + if d1 == fav { 1 } else { 0 };
                 if total_fav >= req_fav {
                     // Solution found! Reconstruct it.
-                    return reconstruct(len, m, fav, &suffixes, &ten_pow);
+                    return reconstruct(len, &modulus, fav, &suffixes, &ten_pow);
                 }
             }
         }
@@ -102,7 +114,13 @@ fn solve_user(user: &User) -> String {
     unreachable!()
 }
 
-fn reconstruct(len: usize, m: usize, fav: u32, suffixes: &[Vec<i32>], ten_pow: &[usize]) -> String {
+fn reconstruct(
+    len: usize,
+    modulus: &Modulus,
+    fav: u32,
+    suffixes: &[Vec<i32>],
+    ten_pow: &[usize],
+) -> String {
     let mut result = String::new();
     let mut current_rem_sum = 0; // accumulated sum % m of chosen digits
     let mut current_fav_count = 0;
@@ -114,11 +132,11 @@ fn reconstruct(len: usize, m: usize, fav: u32, suffixes: &[Vec<i32>], ten_pow: &
         let start_d = if is_first { 1 } else { 0 };
 
         for d in start_d..10 {
-            let term = (d as usize * ten_pow[i]) % m;
-            let next_sum = (current_rem_sum + term) % m;
-            let needed_suffix_rem = (m - next_sum) % m;
+            let term = modulus.mul(u128::from(d), ten_pow[i] as u128);
+            let next_sum = modulus.add(current_rem_sum, term);
+            let needed_suffix_rem = modulus.sub(0, next_sum);
 
-            let suffix_max_fav = suffixes[i][needed_suffix_rem];
+            let suffix_max_fav = suffixes[i][needed_suffix_rem as usize];
             if suffix_max_fav != -1 {
                 let new_fav = current_fav_count + if d == fav { 1 } else { 0 };
                 if new_fav + suffix_max_fav >= req_fav {
@@ -133,15 +151,22 @@ fn reconstruct(len: usize, m: usize, fav: u32, suffixes: &[Vec<i32>], ten_pow: &
     result
 }
 
-fn part1(input: &str) -> usize {
-    let users = parse_input(input);
+fn part1(users: &[User]) -> Result<u64, String> {
     let mut sum: u64 = 0;
     for user in users {
-        let s = solve_user(&user);
-        let val: u64 = s.parse().unwrap();
-        sum += val;
+        let password = solve_user(user);
+        sum = password
+            .parse()
+            .ok()
+            .and_then(|value| sum.checked_add(value))
+            .ok_or_else(|| {
+                user.at.error(format!(
+                    "the passwords up to {password} add up to more than {}",
+                    u64::MAX
+                ))
+            })?;
     }
-    sum as usize
+    Ok(sum)
 }
 
 fn count_valid(user: &User, len: usize) -> usize {
@@ -197,22 +222,18 @@ fn count_valid(user: &User, len: usize) -> usize {
     total
 }
 
-fn part2(input: &str) -> usize {
-    let users = parse_input(input);
+fn part2(users: &[User]) -> usize {
     let mut total = 0;
     for user in users {
         for len in 8..=16 {
-            total += count_valid(&user, len);
+            total += count_valid(user, len);
         }
     }
     total
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    const TEST_INPUT: &str = "\
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
 User 1:
 Favorite Digit: 8
 Favorite Number: 2
@@ -230,13 +251,70 @@ Favorite Digit: 0
 Favorite Number: 3
 ";
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
     #[test]
     fn test_part1() {
-        assert_eq!(part1(TEST_INPUT), 56128);
+        assert_eq!(part1(&parse_input(EXAMPLE).unwrap()), Ok(56128));
     }
 
     #[test]
     fn test_part2() {
-        assert_eq!(part2(TEST_INPUT), 591318956547);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), 591318956547);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input("User 1:\nFavorite Digit: 12\nFavorite Number: 3").err(),
+            Some("line 2, column 17: 12 is not a digit".to_string())
+        );
+        assert!(parse_input("User 1:\nFavorite Digit: 1\nFavorite Number: 0").is_err());
+        assert!(parse_input("User 1:\nFavorite Digit: 1").is_err());
+        assert!(parse_input("Player 1:\nFavorite Digit: 1\nFavorite Number: 3").is_err());
+    }
+
+    fn user(s: &mut Source) -> User<'static> {
+        User {
+            at: parse::lines("User 1:").next().unwrap(),
+            digit: s.int(0u32..=9),
+            number: s.int(1u32..=60),
+        }
+    }
+
+    fn favorites(n: u64, digit: u32) -> usize {
+        n.to_string()
+            .chars()
+            .filter(|&c| c.to_digit(10) == Some(digit))
+            .count()
+    }
+
+    #[test]
+    fn test_password_is_valid() {
+        property::check(user, |user| {
+            let password = solve_user(user);
+            let value: u64 = password.parse().unwrap();
+            !password.starts_with('0')
+                && value.is_multiple_of(user.number as u64)
+                && favorites(value, user.digit) >= password.len().div_ceil(2)
+        });
+    }
+
+    #[test]
+    fn test_count_valid_matches_enumeration() {
+        property::check(
+            |s| (user(s), s.int(1usize..=4)),
+            |(user, len)| {
+                let low = 10u64.pow(*len as u32 - 1).max(1);
+                let valid = (low..10 * low)
+                    .filter(|n| n.is_multiple_of(user.number as u64))
+                    .filter(|&n| favorites(n, user.digit) >= len.div_ceil(2))
+                    .count();
+                count_valid(user, *len) == valid
+            },
+        );
     }
 }
@@ -1,322 +1,242 @@
 // Day 23: Grid Traversal v3
 // https://lovemathboy.github.io/day23.html
 
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::collections::HashMap;
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day23.txt").ok()?;
-    let grids: Vec<&str> = input.trim().split("\n\n").collect();
+use crate::bigint::BigUint;
+use crate::graph::{self, MinCostFlow};
+use crate::grid::{Grid, Pos};
+use crate::parse::{self, Line};
+use crate::render::{self, Color, Picture};
+use crate::trace;
 
-    let mut total_product_p1: u128 = 1;
-    let mut total_product_p2: u128 = 1;
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let grids = parse::blocks(input);
+
+    let mut total_product_p1 = BigUint::from(1u64);
+    let mut total_product_p2 = BigUint::from(1u64);
     let mut solved_count = 0;
 
-    #[cfg(debug_assertions)]
-    println!("Found {} grids.", grids.len());
+    trace::info!("{} grids", grids.len());
+
+    for (i, block) in (1..).zip(&grids) {
+        let Some(maze) = Maze::parse(block.lines())? else {
+            trace::info!("grid {i}: skipped (no start or end)");
+            continue;
+        };
 
-    for (_i, grid_str) in grids.iter().enumerate() {
-        if let Some(score_p1) = solve_grid_part1(grid_str) {
-            #[cfg(debug_assertions)]
-            println!("Grid {}: P1 Minimum score = {}", _i + 1, score_p1);
-            total_product_p1 *= score_p1 as u128;
+        if let Some(score_p1) = solve_grid_part1(&maze) {
+            trace::info!("grid {i}: part 1 minimum score = {score_p1}");
+            total_product_p1 *= BigUint::from(score_p1 as u64);
             solved_count += 1;
         } else {
-            #[cfg(debug_assertions)]
-            println!("Grid {}: Skipped (invalid or empty)", _i + 1);
+            trace::info!("grid {i}: skipped (end unreachable)");
             continue;
         }
 
-        if let Some(score_p2) = solve_grid_part2(grid_str) {
-            #[cfg(debug_assertions)]
-            println!("Grid {}: P2 Minimum score = {}", _i + 1, score_p2);
-            total_product_p2 *= score_p2 as u128;
+        if let Some(score_p2) = solve_grid_part2(&maze) {
+            trace::info!("grid {i}: part 2 minimum score = {score_p2}");
+            total_product_p2 *= BigUint::from(score_p2 as u64);
         } else {
-            #[cfg(debug_assertions)]
-            println!("Grid {}: P2 No solution found", _i + 1);
+            trace::info!("grid {i}: part 2 has no solution");
         }
     }
 
     if solved_count == 0 {
-        return None;
+        return Err("no grid with both S and E".to_string());
     }
 
-    Some((total_product_p1.to_string(), total_product_p2.to_string()))
+    Ok((total_product_p1.to_string(), total_product_p2.to_string()))
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-struct State {
-    cost: usize,
-    position: (usize, usize),
+/// Grid of digit costs with a start `S` and an end `E`, which cost nothing.
+struct Maze {
+    costs: Grid<u32>,
+    start: Pos,
+    end: Pos,
 }
 
-impl Ord for State {
-    fn cmp(&self, other: &State) -> Ordering {
-        other
-            .cost
-            .cmp(&self.cost)
-            .then_with(|| self.position.cmp(&other.position))
-    }
-}
+impl Maze {
+    /// Returns `None` if the grid has no start or no end.
+    fn parse<'a>(lines: impl IntoIterator<Item = Line<'a>>) -> Result<Option<Self>, String> {
+        let grid = Grid::parse(lines, |c| matches!(c, '0'..='9' | 'S' | 'E').then_some(c))?;
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &State) -> Option<Ordering> {
-        Some(self.cmp(other))
+        let (Some(start), Some(end)) = (grid.find(|&c| c == 'S'), grid.find(|&c| c == 'E')) else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            costs: grid.map(|c| c.to_digit(10).unwrap_or(0)),
+            start,
+            end,
+        }))
     }
 }
 
-fn solve_grid_part1(grid_str: &str) -> Option<usize> {
-    if grid_str.trim().is_empty() {
-        return None;
-    }
+/// Cost of the cheapest path from the start to each reachable cell.
+fn path_costs(maze: &Maze) -> HashMap<Pos, u64> {
+    let grid = &maze.costs;
 
-    let lines: Vec<&str> = grid_str.lines().collect();
-    let rows = lines.len();
-    let cols = lines[0].len();
-
-    let mut start_pos = None;
-    let mut end_pos = None;
-    let mut grid = Vec::with_capacity(rows);
-
-    for (r, line) in lines.iter().enumerate() {
-        let mut row_vec = Vec::with_capacity(cols);
-        for (c, char) in line.chars().enumerate() {
-            if char == 'S' {
-                start_pos = Some((r, c));
-                row_vec.push(0);
-            } else if char == 'E' {
-                end_pos = Some((r, c));
-                row_vec.push(0);
-            } else {
-                row_vec.push(char.to_digit(10).unwrap() as usize);
-            }
-        }
-        grid.push(row_vec);
-    }
+    graph::dijkstra(maze.start, |&pos| {
+        grid.neighbours4(pos)
+            .map(|next| (next, u64::from(grid[next])))
+    })
+}
 
-    let start_pos = start_pos?;
-    let end_pos = end_pos?;
+fn solve_grid_part1(maze: &Maze) -> Option<usize> {
+    path_costs(maze).get(&maze.end).map(|&cost| cost as usize)
+}
 
-    let mut pq = BinaryHeap::new();
-    pq.push(State {
-        cost: 0,
-        position: start_pos,
+/// Cells of a cheapest path from the start to the end, if the end can be reached.
+fn cheapest_path(maze: &Maze) -> Option<Vec<Pos>> {
+    let grid = &maze.costs;
+    let costs = path_costs(maze);
+
+    // moves that belong to a cheapest path
+    let tight = |from: Pos, to: Pos| {
+        costs
+            .get(&from)
+            .is_some_and(|&cost| costs.get(&to) == Some(&(cost + u64::from(grid[to]))))
+    };
+
+    // number of such moves left to the end: it decreases along the path,
+    // even through cells of cost 0
+    let moves_left = graph::bfs01(maze.end, |&pos| {
+        grid.neighbours4(pos)
+            .filter(move |&before| tight(before, pos))
+            .map(|before| (before, 1))
     });
 
-    let mut min_costs: HashMap<(usize, usize), usize> = HashMap::new();
-    min_costs.insert(start_pos, 0);
-
-    while let Some(State {
-        cost,
-        position: (r, c),
-    }) = pq.pop()
-    {
-        if (r, c) == end_pos {
-            return Some(cost);
-        }
-
-        if cost > *min_costs.get(&(r, c)).unwrap_or(&usize::MAX) {
-            continue;
-        }
-
-        let deltas = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-
-        for (dr, dc) in deltas.iter() {
-            let nr = r as isize + dr;
-            let nc = c as isize + dc;
-
-            if nr >= 0 && nr < rows as isize && nc >= 0 && nc < cols as isize {
-                let nr = nr as usize;
-                let nc = nc as usize;
-                let new_cost = cost + grid[nr][nc];
-
-                if new_cost < *min_costs.get(&(nr, nc)).unwrap_or(&usize::MAX) {
-                    min_costs.insert((nr, nc), new_cost);
-                    pq.push(State {
-                        cost: new_cost,
-                        position: (nr, nc),
-                    });
-                }
-            }
-        }
+    let mut path = vec![maze.start];
+    let mut pos = maze.start;
+    while pos != maze.end {
+        let left = moves_left.get(&pos)?;
+        pos = grid
+            .neighbours4(pos)
+            .find(|&next| tight(pos, next) && moves_left.get(&next) == Some(&(left - 1)))?;
+        path.push(pos);
     }
-
-    None
+    Some(path)
 }
 
-// MCMF Implementation for Part 2
+// Part 2: two disjoint paths are a flow of 2 where every cell has a capacity of 1.
 
-#[derive(Clone, Debug)]
-struct Edge {
-    to: usize,
-    cap: i32,
-    cost: i64,
-    rev: usize,
-}
-
-struct MinCostMaxFlow {
-    graph: Vec<Vec<Edge>>,
-}
-
-impl MinCostMaxFlow {
-    fn new(n: usize) -> Self {
-        Self {
-            graph: vec![Vec::new(); n],
-        }
-    }
+fn solve_grid_part2(maze: &Maze) -> Option<i64> {
+    let grid = &maze.costs;
 
-    fn add_edge(&mut self, from: usize, to: usize, cap: i32, cost: i64) {
-        let rev_from = self.graph[to].len();
-        let rev_to = self.graph[from].len();
-        self.graph[from].push(Edge {
-            to,
-            cap,
-            cost,
-            rev: rev_from,
-        });
-        self.graph[to].push(Edge {
-            to: from,
-            cap: 0,
-            cost: -cost,
-            rev: rev_to,
-        });
-    }
+    // Nodes: 2 per grid cell -> 2 * rows * cols
+    let num_nodes = 2 * grid.rows() * grid.cols();
+    let mut mcmf = MinCostFlow::new(num_nodes);
 
-    fn solve(&mut self, source: usize, sink: usize, required_flow: i32) -> Option<i64> {
-        let n = self.graph.len();
-        let mut total_flow = 0;
-        let mut min_cost = 0;
-
-        // Potentials for SPFA / Dijkstra if we had negative edges (SPFA is safer here given simple grid graph with potential cycles if costs were negative, though here costs are non-negative, but residual edges can be negative)
-        // With simple SPFA:
-        while total_flow < required_flow {
-            let mut dist = vec![i64::MAX; n];
-            let mut parent_node = vec![usize::MAX; n];
-            let mut parent_edge = vec![usize::MAX; n];
-            let mut in_queue = vec![false; n];
-            let mut queue = VecDeque::new();
-
-            dist[source] = 0;
-            queue.push_back(source);
-            in_queue[source] = true;
-
-            while let Some(u) = queue.pop_front() {
-                in_queue[u] = false;
-                for (i, e) in self.graph[u].iter().enumerate() {
-                    if e.cap > 0 && dist[e.to] > dist[u].saturating_add(e.cost) {
-                        dist[e.to] = dist[u] + e.cost;
-                        parent_node[e.to] = u;
-                        parent_edge[e.to] = i;
-                        if !in_queue[e.to] {
-                            queue.push_back(e.to);
-                            in_queue[e.to] = true;
-                        }
-                    }
-                }
-            }
+    let source_node = 2 * grid.index_of(maze.start);
+    let sink_node = 2 * grid.index_of(maze.end) + 1;
 
-            if dist[sink] == i64::MAX {
-                return None; // Cannot push flow
-            }
+    for (pos, &cost) in grid.iter() {
+        let in_n = 2 * grid.index_of(pos);
+        let out_n = in_n + 1;
 
-            let push = required_flow - total_flow;
-            // The flow along shortest path in residual graph is usually determined by min capacity,
-            // but here capacities are small integers.
-            // Let's find flow bottleneck.
-            let mut flow = push;
-            let mut curr = sink;
-            while curr != source {
-                let p = parent_node[curr];
-                let idx = parent_edge[curr];
-                flow = flow.min(self.graph[p][idx].cap);
-                curr = p;
-            }
+        let cap = if pos == maze.start || pos == maze.end {
+            2
+        } else {
+            1
+        };
 
-            total_flow += flow;
-            min_cost += flow as i64 * dist[sink];
+        // Edge In -> Out
+        mcmf.add_edge(in_n, out_n, cap, i64::from(cost));
 
-            curr = sink;
-            while curr != source {
-                let p = parent_node[curr];
-                let idx = parent_edge[curr];
+        // Edges to neighbors
+        for next in grid.neighbours4(pos) {
+            let neighbor_in = 2 * grid.index_of(next);
+            // Out -> Neighbor In
+            mcmf.add_edge(out_n, neighbor_in, 1, 0);
+        }
+    }
 
-                self.graph[p][idx].cap -= flow;
-                let rev_idx = self.graph[p][idx].rev;
-                self.graph[curr][rev_idx].cap += flow;
+    mcmf.solve(source_node, sink_node, 2)
+}
 
-                curr = p;
+/// The mazes with a cheapest path of part 1 highlighted.
+pub fn render(input: &str) -> Result<Vec<Picture>, String> {
+    let mut pictures = Vec::new();
+    for block in parse::blocks(input) {
+        let Some(maze) = Maze::parse(block.lines())? else {
+            continue;
+        };
+        let path = cheapest_path(&maze).unwrap_or_default();
+        let cells = Grid::from_fn(maze.costs.rows(), maze.costs.cols(), |pos| match pos {
+            _ if pos == maze.start => 'S',
+            _ if pos == maze.end => 'E',
+            _ => char::from(b'0' + maze.costs[pos] as u8),
+        });
+        pictures.push(Picture::Terminal(render::ansi_grid(&cells, |pos| {
+            if cells[pos].is_ascii_uppercase() {
+                Some(Color::Red)
+            } else {
+                path.contains(&pos).then_some(Color::Yellow)
             }
-        }
-
-        Some(min_cost)
+        })));
     }
+    Ok(pictures)
 }
 
-fn solve_grid_part2(grid_str: &str) -> Option<i64> {
-    if grid_str.trim().is_empty() {
-        return None;
+/// Small grid in the input format, with a start and an end.
+pub const EXAMPLE: &str = "\
+S5138
+29475
+1326E
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::{self, Source};
+
+    #[test]
+    fn test_maze() {
+        let maze = Maze::parse(parse::lines(EXAMPLE)).unwrap().unwrap();
+        assert_eq!((maze.start, maze.end), ((0, 0), (2, 4)));
+        assert_eq!(maze.costs[(1, 2)], 4);
+        assert_eq!(solve_grid_part1(&maze), Some(14));
+
+        let path = cheapest_path(&maze).unwrap();
+        assert_eq!((path[0], path[path.len() - 1]), (maze.start, maze.end));
+        let cost: u32 = path.iter().map(|&pos| maze.costs[pos]).sum();
+        assert_eq!(cost, 14);
+
+        assert!(Maze::parse(parse::lines("S12\n34E\n")).unwrap().is_some());
+        assert!(Maze::parse(parse::lines("S12\n345\n")).unwrap().is_none());
+        assert!(Maze::parse(parse::lines("S1x\n34E\n")).is_err());
     }
 
-    let lines: Vec<&str> = grid_str.lines().collect();
-    let rows = lines.len();
-    let cols = lines[0].len();
-
-    let mut start_pos = None;
-    let mut end_pos = None;
-
-    // Parse to find S and E
-    for (r, line) in lines.iter().enumerate() {
-        for (c, char) in line.chars().enumerate() {
-            if char == 'S' {
-                start_pos = Some((r, c));
-            } else if char == 'E' {
-                end_pos = Some((r, c));
-            }
-        }
+    /// Digit grid with distinct start and end cells.
+    fn grid(s: &mut Source) -> String {
+        let mut grid = s.grid(2..=5, 2..=5, |s| char::from(b'0' + s.int(1u8..=9)));
+        let cells: Vec<_> = grid.positions().collect();
+        let start = s.int(0..=cells.len() - 1);
+        let end = (start + s.int(1..=cells.len() - 1)) % cells.len();
+        grid[cells[start]] = 'S';
+        grid[cells[end]] = 'E';
+        grid.to_string()
     }
 
-    let start_pos = start_pos?;
-    let end_pos = end_pos?;
-
-    // Nodes: 2 per grid cell -> 2 * rows * cols
-    let num_nodes = 2 * rows * cols;
-    let mut mcmf = MinCostMaxFlow::new(num_nodes);
-
-    let source_node = 2 * (start_pos.0 * cols + start_pos.1);
-    let sink_node = 2 * (end_pos.0 * cols + end_pos.1) + 1;
-
-    for (r, line) in lines.iter().enumerate() {
-        for c in 0..cols {
-            let char = line.chars().nth(c).unwrap();
-
-            let in_n = 2 * (r * cols + c);
-            let out_n = in_n + 1;
-
-            let (cap, cost) = if char == 'S' || char == 'E' {
-                (2, 0)
-            } else {
-                (1, char.to_digit(10).unwrap() as i64)
+    #[test]
+    fn test_properties() {
+        property::check(grid, |grid| {
+            let total: usize = grid.chars().filter_map(|c| c.to_digit(10)).sum::<u32>() as usize;
+            let Ok(Some(maze)) = Maze::parse(parse::lines(grid)) else {
+                return false;
+            };
+            let Some(one) = solve_grid_part1(&maze) else {
+                return false;
             };
 
-            // Edge In -> Out
-            mcmf.add_edge(in_n, out_n, cap, cost);
-
-            // Edges to neighbors
-            let deltas = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-            for (dr, dc) in deltas.iter() {
-                let nr = r as isize + dr;
-                let nc = c as isize + dc;
-
-                if nr >= 0 && nr < rows as isize && nc >= 0 && nc < cols as isize {
-                    let nr = nr as usize;
-                    let nc = nc as usize;
-                    let neighbor_in = 2 * (nr * cols + nc);
-                    // Out -> Neighbor In
-                    mcmf.add_edge(out_n, neighbor_in, 1, 0);
-                }
-            }
-        }
-    }
+            let path = cheapest_path(&maze).unwrap_or_default();
+            let path_cost: u32 = path.iter().map(|&pos| maze.costs[pos]).sum();
 
-    mcmf.solve(source_node, sink_node, 2)
+            // each of the two disjoint paths costs at least as much as the best single path
+            path_cost as usize == one
+                && one <= total
+                && solve_grid_part2(&maze).is_none_or(|two| two >= 2 * one as i64)
+        });
+    }
 }
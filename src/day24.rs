@@ -1,64 +1,96 @@
 // Day 24: Pyramid
 // https://lovemathboy.github.io/day24.html
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day24.txt").ok()?;
-    Some((part1(&input).to_string(), part2(&input).to_string()))
+use crate::modint::Modulus;
+use crate::parse;
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
+    let numbers = parse_input(input)?;
+    Ok((part1(&numbers)?.to_string(), part2(&numbers)?.to_string()))
 }
 
-fn part1(input: &str) -> u64 {
-    let mut a: Vec<u64> = input
-        .split_whitespace()
-        .map(|s| s.parse().unwrap())
-        .collect();
+/// Longest pyramid base accepted once decompressed.
+const MAX_LENGTH: u64 = 1 << 26;
+
+/// Numbers separated by blanks, on any number of lines.
+fn parse_input(input: &str) -> Result<Vec<u64>, String> {
+    let mut numbers = Vec::new();
+    for mut line in parse::lines(input) {
+        while !line.at_end() {
+            numbers.push(line.int()?);
+        }
+    }
+    Ok(numbers)
+}
+
+fn overflow() -> String {
+    "the sum of the heights overflows".to_string()
+}
 
-    let mut r = 0;
+fn part1(numbers: &[u64]) -> Result<u64, String> {
+    let mut a = numbers.to_vec();
+
+    let mut r: u64 = 0;
     while !a.is_empty() {
-        r += a.iter().sum::<u64>();
+        r = a
+            .iter()
+            .try_fold(r, |r, &x| r.checked_add(x))
+            .ok_or_else(overflow)?;
         let mut b = Vec::with_capacity(a.len().saturating_sub(1));
         for i in 0..a.len().saturating_sub(1) {
-            b.push(a[i].max(a[i + 1]) + 1);
+            b.push(a[i].max(a[i + 1]).checked_add(1).ok_or_else(overflow)?);
         }
         a = b;
     }
-    r
+    Ok(r)
 }
 
-fn part2(input: &str) -> u64 {
-    let raw_nums: Vec<u64> = input
-        .split_whitespace()
-        .map(|s| s.parse().unwrap())
-        .collect();
+/// Expands the runs of 5 numbers: initial value, b, c, modulus and length.
+fn decompress(numbers: &[u64]) -> Result<Vec<u64>, String> {
+    if !numbers.len().is_multiple_of(5) {
+        return Err(format!("{} numbers do not make runs of 5", numbers.len()));
+    }
+    let length: u64 = numbers
+        .chunks(5)
+        .map(|run| run[4])
+        .fold(0, u64::saturating_add);
+    if length > MAX_LENGTH {
+        return Err(format!(
+            "the decompressed pyramid base of {length} is too long"
+        ));
+    }
 
     let mut a = Vec::new();
-
-    // Decompress
-    for chunk in raw_nums.chunks(5) {
-        if chunk.len() < 5 {
-            break;
-        }
+    for (i, chunk) in numbers.chunks(5).enumerate() {
         let initial = chunk[0];
         let b = chunk[1];
         let c = chunk[2];
-        let mod_val = chunk[3];
+        if chunk[3] == 0 {
+            return Err(format!("run {}: the modulus is 0", i + 1));
+        }
+        let modulus = Modulus::new(u128::from(chunk[3]));
         let n = chunk[4];
 
+        // b * current may not fit in a u64
         let mut current = initial;
         a.push(current);
         for _ in 1..n {
-            current = (b * current + c) % mod_val;
+            current = modulus.add(modulus.mul(b.into(), current.into()), c.into()) as u64;
             a.push(current);
         }
     }
+    Ok(a)
+}
 
+fn part2(numbers: &[u64]) -> Result<u64, String> {
+    let a = decompress(numbers)?;
     let n = a.len();
 
     // Term 1: Sum of heights
-    // sum_{h=0}^{N-1} h * (N - h)
-    let mut term1 = 0;
-    for h in 0..n {
-        term1 += (h as u64) * ((n - h) as u64);
-    }
+    // sum_{h=0}^{N-1} h * (N - h) = (N - 1) * N * (N + 1) / 6
+    let n128 = n as u128;
+    let term1 =
+        u64::try_from(n128.saturating_sub(1) * n128 * (n128 + 1) / 6).map_err(|_| overflow())?;
 
     // Term 2: Sum of max(subarray)
     // Left bound: index of previous element >= A[i] (so strictly less to left)
@@ -103,30 +135,67 @@ fn part2(input: &str) -> u64 {
         stack.push(i);
     }
 
-    let mut term2 = 0;
+    let mut term2: u64 = 0;
     for i in 0..n {
         let l = left_bound[i];
         let r = right_bound[i];
         let count = ((i as i64 - l) * (r - i as i64)) as u64;
-        term2 += count * a[i];
+        term2 = count
+            .checked_mul(a[i])
+            .and_then(|max| term2.checked_add(max))
+            .ok_or_else(overflow)?;
     }
 
-    term1 + term2
+    term1.checked_add(term2).ok_or_else(overflow)
 }
 
+/// Example of the puzzle statement (part 2 reads it as two compressed runs).
+pub const EXAMPLE: &str = "4 9 2 7 9 1 2 3 61 5";
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::property;
 
     #[test]
     fn test_part1() {
-        let input = "4 9 2 7 9";
-        assert_eq!(part1(input), 139);
+        assert_eq!(part1(&[4, 9, 2, 7, 9]), Ok(139));
     }
 
     #[test]
     fn test_part2() {
-        let input = "4 9 2 7 9 1 2 3 61 5";
-        assert_eq!(part2(input), 1618);
+        assert_eq!(part2(&parse_input(EXAMPLE).unwrap()), Ok(1618));
+        assert!(part2(&[u64::MAX - 1, 0, 0, u64::MAX, 3]).is_err());
+        // the sum of the heights alone overflows
+        assert_eq!(part2(&[1, 0, 0, 1, 5_000_000]), Err(overflow()));
+    }
+
+    #[test]
+    fn test_part1_overflow() {
+        assert_eq!(part1(&[u64::MAX, 0]), Err(overflow()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_input("1 2\n\n3\n"), Ok(vec![1, 2, 3]));
+        assert_eq!(
+            parse_input("1 2\n3 x"),
+            Err("line 2, column 3: expected a number, found \"x\"".to_string())
+        );
+        assert!(decompress(&[1, 2, 3, 4]).is_err());
+        assert!(decompress(&[1, 2, 3, 0, 4]).is_err());
+        assert!(decompress(&[1, 2, 3, 4, u64::MAX]).is_err());
+    }
+
+    #[test]
+    fn test_part2_uncompressed_matches_part1() {
+        property::check(
+            |s| s.vec(1..=12, |s| s.int(0u64..=50)),
+            |values| {
+                // a run of one value: initial value, b, c, modulus, length
+                let runs: Vec<u64> = values.iter().flat_map(|&v| [v, 0, 0, 1, 1]).collect();
+                part2(&runs) == part1(values)
+            },
+        );
     }
 }
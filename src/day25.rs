@@ -1,291 +1,289 @@
 // Day 25: Christmas Tree Farm
 // https://lovemathboy.github.io/day25.html
 
-pub fn solve() -> Option<(String, String)> {
-    let input = std::fs::read_to_string("inputs/day25.txt").ok()?;
-
+use crate::graph;
+use crate::grid::{Grid, Pos};
+use crate::inspect::{self, Report};
+use crate::parse::{self, Line};
+use crate::render::{self, Color, Picture};
+use crate::trace;
+
+pub fn solve(input: &str) -> Result<(String, String), String> {
     //  Pokédex number of Shaymin: 492
 
-    Some((part1(&input).to_string(), "492".to_string()))
+    Ok((part1(input)?.to_string(), "492".to_string()))
+}
+
+/// Shapes and regions, and whether part 1 supports the shapes.
+pub fn inspect(input: &str, report: &mut Report) -> Result<(), String> {
+    let (shapes, regions) = parse_input(input)?;
+    report.stat("shapes", shapes.len());
+    report.stat(
+        "shape areas",
+        inspect::range(shapes.iter().map(Shape::area)),
+    );
+    report.stat("regions", regions.len());
+    report.stat(
+        "region areas",
+        inspect::range(regions.iter().map(|r| r.grid.rows() * r.grid.cols())),
+    );
+    report.stat(
+        "presents per region",
+        inspect::range(regions.iter().map(|r| r.presents.iter().sum::<usize>())),
+    );
+
+    let others: Vec<String> = shapes
+        .iter()
+        .filter(|shape| shape.area() != 2)
+        .map(|shape| shape.id.to_string())
+        .collect();
+    if !others.is_empty() {
+        report.violation(format!(
+            "part 1 only supports dominoes (shapes of area 2), unlike shape(s) {}",
+            others.join(", ")
+        ));
+    }
+    Ok(())
 }
 
+/// Largest region accepted, in cells.
+const MAX_REGION_AREA: usize = 1 << 24;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct Shape {
     id: usize,
-    grid: Vec<Vec<bool>>,
-    width: usize,
-    height: usize,
+    grid: Grid<bool>, // cropped to the bounding box of the shape
 }
 
 impl Shape {
-    fn new(id: usize, lines: &[&str]) -> Self {
-        let height = lines.len();
-        let width = lines[0].len();
-        let mut min_x = width;
-        let mut max_x = 0;
-        let mut min_y = height;
-        let mut max_y = 0;
-        let mut has_points = false;
-
-        for (y, line) in lines.iter().enumerate() {
-            for (x, ch) in line.chars().enumerate() {
-                if ch == '#' {
-                    min_x = min_x.min(x);
-                    max_x = max_x.max(x);
-                    min_y = min_y.min(y);
-                    max_y = max_y.max(y);
-                    has_points = true;
-                }
-            }
-        }
-
-        if !has_points {
-            return Shape {
+    fn parse<'a>(id: usize, lines: impl IntoIterator<Item = Line<'a>>) -> Result<Self, String> {
+        let full = Grid::parse(lines, cell)?;
+
+        let filled: Vec<Pos> = full
+            .iter()
+            .filter(|&(_, &c)| c)
+            .map(|(pos, _)| pos)
+            .collect();
+        if filled.is_empty() {
+            return Ok(Shape {
                 id,
-                grid: vec![],
-                width: 0,
-                height: 0,
-            };
+                grid: Grid::new(0, 0, false),
+            });
         }
 
-        let new_width = max_x - min_x + 1;
-        let new_height = max_y - min_y + 1;
-        let mut grid = vec![vec![false; new_width]; new_height];
+        let min_y = filled.iter().map(|p| p.0).min().unwrap_or(0);
+        let max_y = filled.iter().map(|p| p.0).max().unwrap_or(0);
+        let min_x = filled.iter().map(|p| p.1).min().unwrap_or(0);
+        let max_x = filled.iter().map(|p| p.1).max().unwrap_or(0);
 
-        for (y, row) in grid.iter_mut().enumerate() {
-            for (x, cell) in row.iter_mut().enumerate() {
-                let original_char = lines[min_y + y].chars().nth(min_x + x).unwrap();
-                if original_char == '#' {
-                    *cell = true;
-                }
-            }
-        }
-
-        Shape {
+        Ok(Shape {
             id,
-            grid,
-            width: new_width,
-            height: new_height,
-        }
+            grid: full
+                .view((min_y, min_x), max_y - min_y + 1, max_x - min_x + 1)
+                .to_grid(),
+        })
+    }
+
+    fn area(&self) -> usize {
+        self.grid.iter().filter(|&(_, &c)| c).count()
     }
 }
 
 struct Region {
     id: usize,
-    width: usize,
-    height: usize,
-    grid: Vec<Vec<bool>>,          // true if obstructed or occupied
-    required_presents: Vec<usize>, // list of shape IDs to fit
+    grid: Grid<bool>,     // true if obstructed or occupied
+    presents: Vec<usize>, // number of presents to fit, per shape ID
 }
 
-fn parse_input(input: &str) -> (Vec<Shape>, Vec<Region>) {
-    let chunks: Vec<&str> = input.split("\n\n").collect();
+/// Obstructed or occupied cells are `#`.
+fn cell(ch: char) -> Option<bool> {
+    match ch {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    }
+}
 
+fn parse_input(input: &str) -> Result<(Vec<Shape>, Vec<Region>), String> {
     let mut shapes = Vec::new();
     let mut regions = Vec::new();
-    let mut region_id = 1;
 
-    for chunk in chunks {
-        let chunk = chunk.trim();
-        if chunk.is_empty() {
+    for block in parse::blocks(input) {
+        let mut header = block.header();
+        let first = header.int()?;
+
+        if header.eat(":") {
+            // Shape: "0:"
+            header.end()?;
+            shapes.push(Shape::parse(first, block.body())?);
             continue;
         }
 
-        let first_line = chunk.lines().next().unwrap();
+        // Region: "5x12: 1 0 ...", optionally followed by its grid
+        let height: usize = first;
+        header.literal("x")?;
+        let width: usize = header.int()?;
+        header.literal(":")?;
 
-        if first_line.contains(':') && !first_line.contains('x') {
-            // Shape: "0:"
-            let mut lines = chunk.lines();
-            let header = lines.next().unwrap();
-            let id_str = header.trim_end_matches(':');
-            let id: usize = id_str.parse().unwrap();
-
-            let mut shape_lines = Vec::new();
-            for line in lines {
-                shape_lines.push(line);
-            }
-            shapes.push(Shape::new(id, &shape_lines));
-        } else {
-            // Region: "5x12: 1 0 ..."
-            let mut lines = chunk.lines();
-            let header = lines.next().unwrap();
-
-            let (dims_part, reqs_part) = header.split_once(": ").unwrap();
-            let (h_str, w_str) = dims_part.split_once('x').unwrap();
-            let height: usize = h_str.trim().parse().unwrap();
-            let width: usize = w_str.trim().parse().unwrap();
-
-            let counts: Vec<usize> = reqs_part
-                .split_whitespace()
-                .map(|s| s.parse().unwrap())
-                .collect();
-
-            let mut required_presents = Vec::new();
-            for (shape_idx, &count) in counts.iter().enumerate() {
-                for _ in 0..count {
-                    required_presents.push(shape_idx);
-                }
-            }
+        if height
+            .checked_mul(width)
+            .is_none_or(|area| area > MAX_REGION_AREA)
+        {
+            return Err(block
+                .header()
+                .error(format!("region {height}x{width} is too large")));
+        }
 
-            let mut grid = vec![vec![false; width]; height];
-            for (y, line) in lines.enumerate() {
-                if y >= height {
-                    break;
-                }
-                for (x, ch) in line.chars().enumerate() {
-                    if x >= width {
-                        break;
-                    }
-                    if ch == '#' {
-                        grid[y][x] = true;
-                    }
-                }
+        let mut presents = Vec::new();
+        while !header.at_end() {
+            presents.push(header.int()?);
+        }
+
+        let grid = if block.body().next().is_none() {
+            Grid::new(height, width, false)
+        } else {
+            let grid = Grid::parse(block.body(), cell)?;
+            if (grid.rows(), grid.cols()) != (height, width) {
+                return Err(block.header().error(format!(
+                    "region {height}x{width} has a {}x{} grid",
+                    grid.rows(),
+                    grid.cols()
+                )));
             }
+            grid
+        };
 
-            regions.push(Region {
-                id: region_id,
-                width,
-                height,
-                grid,
-                required_presents,
-            });
-            region_id += 1;
-        }
+        regions.push(Region {
+            id: regions.len() + 1,
+            grid,
+            presents,
+        });
     }
 
     shapes.sort_by_key(|s| s.id);
 
-    (shapes, regions)
+    for region in &regions {
+        let unknown = region
+            .presents
+            .iter()
+            .enumerate()
+            .find(|&(id, &count)| count > 0 && !shapes.iter().any(|s| s.id == id));
+        if let Some((id, _)) = unknown {
+            return Err(format!("region {} needs unknown shape {id}", region.id));
+        }
+    }
+
+    Ok((shapes, regions))
 }
 
 fn solve_matching(region: &Region, required_dominoes: usize) -> bool {
+    dominoes(region).len() >= required_dominoes
+}
+
+/// Most dominoes fitting in the free cells of a region, as pairs of cells.
+fn dominoes(region: &Region) -> Vec<(Pos, Pos)> {
+    let grid = &region.grid;
     let mut black_nodes = Vec::new();
     let mut white_nodes = Vec::new();
-    let mut grid_ids = vec![vec![None; region.width]; region.height];
-
-    for (y, row) in grid_ids.iter_mut().enumerate() {
-        for (x, cell) in row.iter_mut().enumerate() {
-            if !region.grid[y][x] {
-                // If free
-                if (x + y) % 2 == 0 {
-                    *cell = Some(black_nodes.len());
-                    black_nodes.push((y, x));
-                } else {
-                    *cell = Some(white_nodes.len());
-                    white_nodes.push((y, x));
-                }
-            }
-        }
-    }
-
-    if black_nodes.len() < required_dominoes || white_nodes.len() < required_dominoes {
-        return false;
-    }
-
-    let mut adj = vec![Vec::new(); black_nodes.len()];
-    for (u_idx, &(y, x)) in black_nodes.iter().enumerate() {
-        let neighbors = [
-            (y.wrapping_sub(1), x),
-            (y + 1, x),
-            (y, x.wrapping_sub(1)),
-            (y, x + 1),
-        ];
-
-        for &(ny, nx) in &neighbors {
-            if ny < region.height
-                && nx < region.width
-                && !region.grid[ny][nx]
-                && (nx + ny) % 2 != 0
-                && let Some(v_idx) = grid_ids[ny][nx]
-            {
-                adj[u_idx].push(v_idx);
+    let mut grid_ids = Grid::new(grid.rows(), grid.cols(), None);
+
+    for ((y, x), &obstructed) in grid.iter() {
+        if !obstructed {
+            // If free
+            if (x + y) % 2 == 0 {
+                grid_ids[(y, x)] = Some(black_nodes.len());
+                black_nodes.push((y, x));
+            } else {
+                grid_ids[(y, x)] = Some(white_nodes.len());
+                white_nodes.push((y, x));
             }
         }
     }
 
-    let mut match_pair = vec![None; white_nodes.len()]; // white -> black
-    let mut vis = vec![false; black_nodes.len()];
-    let mut matches = 0;
-
-    for u in 0..black_nodes.len() {
-        vis.fill(false);
-        if dfs(u, &adj, &mut match_pair, &mut vis) {
-            matches += 1;
-        }
-    }
-
-    matches >= required_dominoes
-}
+    // the neighbours of a black cell are white
+    let adj: Vec<Vec<usize>> = black_nodes
+        .iter()
+        .map(|&pos| {
+            grid.neighbours4(pos)
+                .filter_map(|next| grid_ids[next])
+                .collect()
+        })
+        .collect();
 
-fn dfs(u: usize, adj: &[Vec<usize>], match_pair: &mut [Option<usize>], vis: &mut [bool]) -> bool {
-    vis[u] = true;
-    for &v in &adj[u] {
-        if match_pair[v].is_none()
-            || (!vis[match_pair[v].unwrap()] && dfs(match_pair[v].unwrap(), adj, match_pair, vis))
-        {
-            match_pair[v] = Some(u);
-            return true;
-        }
-    }
-    false
+    graph::matching(&adj, white_nodes.len())
+        .into_iter()
+        .zip(white_nodes)
+        .filter_map(|(black, white)| Some((black_nodes[black?], white)))
+        .collect()
 }
 
-fn part1(input: &str) -> usize {
-    let (shapes, regions) = parse_input(input);
+fn part1(input: &str) -> Result<usize, String> {
+    let (shapes, regions) = parse_input(input)?;
     let mut sum_ids = 0;
 
     // Check if dominoes
-    let shape_areas: Vec<usize> = shapes
-        .iter()
-        .map(|s| s.grid.iter().flatten().filter(|&&c| c).count())
-        .collect();
+    let shape_areas: Vec<usize> = shapes.iter().map(Shape::area).collect();
 
     let all_dominoes = shape_areas.iter().all(|&a| a == 2);
     if !all_dominoes {
-        println!("Warning: Not all shapes are area 2. This solver only supports dominoes.");
-        return 0;
+        trace::warning!("not all shapes have an area of 2: this solver only supports dominoes");
+        return Ok(0);
     }
 
     for region in &regions {
-        let mut required_count = 0;
-        for _ in &region.required_presents {
-            // Simply count items
-            required_count += 1;
-        }
-
-        let initial_free_area = region.grid.iter().flatten().filter(|&&c| !c).count();
-        if initial_free_area < required_count * 2 {
-            println!("Region {} too small.", region.id);
+        let required_count = region
+            .presents
+            .iter()
+            .fold(0usize, |total, &count| total.saturating_add(count));
+
+        let initial_free_area = region.grid.iter().filter(|&(_, &c)| !c).count();
+        if initial_free_area < required_count.saturating_mul(2) {
+            trace::info!("region {}: too small", region.id);
             continue;
         }
 
         if solve_matching(region, required_count) {
-            #[cfg(debug_assertions)]
-            println!("Region {} fits!", region.id);
+            trace::debug!("region {}: the presents fit", region.id);
             sum_ids += region.id;
         } else {
-            #[cfg(debug_assertions)]
-            println!("Region {} does not fit.", region.id);
+            trace::debug!("region {}: the presents do not fit", region.id);
         }
     }
 
-    sum_ids
+    Ok(sum_ids)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// The regions with as many of their dominoes as fit, each drawn with its own letter.
+pub fn render(input: &str) -> Result<Vec<Picture>, String> {
+    const COLORS: [Color; 4] = [Color::Green, Color::Yellow, Color::Blue, Color::Red];
 
-    #[test]
-    fn test_part1() {
-        // NOTE: The example is NOT a domino tiling problem.
-        // It has mixed shapes.
-        // However, we confirmed the user's input is strictly dominoes.
-        // This test will likely FAIL part1 if generic shapes were passed.
-        // But part1 now strictly assumes dominoes.
-        //
-        // We can create a synthetic domino test.
-        let input = "\
+    let (shapes, regions) = parse_input(input)?;
+    if shapes.iter().any(|shape| shape.area() != 2) {
+        return Err("only dominoes (shapes of area 2) can be placed".to_string());
+    }
+
+    let mut pictures = Vec::new();
+    for region in &regions {
+        let required = region.presents.iter().sum::<usize>();
+        let mut cells = Grid::from_fn(region.grid.rows(), region.grid.cols(), |pos| {
+            (if region.grid[pos] { '#' } else { '.' }, None)
+        });
+        for (i, (a, b)) in dominoes(region).into_iter().take(required).enumerate() {
+            let letter = char::from(b'a' + (i % 26) as u8);
+            cells[a] = (letter, Some(COLORS[i % COLORS.len()]));
+            cells[b] = cells[a];
+        }
+        let letters = Grid::from_fn(cells.rows(), cells.cols(), |pos| cells[pos].0);
+        pictures.push(Picture::Terminal(render::ansi_grid(&letters, |pos| {
+            cells[pos].1
+        })));
+    }
+    Ok(pictures)
+}
+
+/// Synthetic domino example: the example of the puzzle statement mixes shapes,
+/// which part 1 does not support.
+pub const EXAMPLE: &str = "\
 0:
 ##
 
@@ -298,21 +296,117 @@ mod tests {
 ....
 ....
 ....";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property;
+
+    #[test]
+    fn test_part1() {
+        // NOTE: The example is NOT a domino tiling problem.
+        // It has mixed shapes.
+        // However, we confirmed the user's input is strictly dominoes.
+        // This test will likely FAIL part1 if generic shapes were passed.
+        // But part1 now strictly assumes dominoes.
+        //
+        // We can create a synthetic domino test.
         // 4x4 grid (16 cells).
         // 4 horizontal dominoes (shape 0) + 2 vertical dominoes (shape 1). Total 6 dominoes (12 cells).
         // Should fit easily.
 
-        assert_eq!(part1(input), 1);
+        assert_eq!(part1(EXAMPLE), Ok(1));
 
         let input_fail = "\
 0:
 ##
 
 2x2: 3
-....
-....";
+..
+..";
         // 2x2 grid (4 cells).
         // 3 dominoes (6 cells). Impossible.
-        assert_eq!(part1(input_fail), 0);
+        assert_eq!(part1(input_fail), Ok(0));
+    }
+
+    #[test]
+    fn test_explain() {
+        let messages = trace::capture(trace::Level::Debug, || {
+            assert_eq!(part1("0:\n##\n\n2x2: 3\n\n1x2: 1\n"), Ok(2));
+        });
+        assert_eq!(
+            messages,
+            ["region 1: too small", "region 2: the presents fit"]
+        );
+
+        let messages = trace::capture(trace::Level::Warn, || {
+            assert_eq!(part1("0:\n###\n\n3x3: 1\n"), Ok(0));
+        });
+        assert_eq!(
+            messages,
+            ["warning: not all shapes have an area of 2: this solver only supports dominoes"]
+        );
+    }
+
+    #[test]
+    fn test_inspect() {
+        let mut report = Report::new(EXAMPLE);
+        assert_eq!(inspect(EXAMPLE, &mut report), Ok(()));
+        assert!(report.is_valid());
+        assert!(report.to_string().contains("  presents per region  6\n"));
+
+        let input = "0:\n##\n\n1:\n###\n\n2:\n#\n\n3x3: 1 1 1\n";
+        let mut report = Report::new(input);
+        assert_eq!(inspect(input, &mut report), Ok(()));
+        assert!(report.to_string().ends_with(
+            "  ❌ part 1 only supports dominoes (shapes of area 2), unlike shape(s) 1, 2\n"
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_input("0:\n##\n\n4y4: 1\n").is_err());
+        assert!(parse_input("0:\n##\n\n4x4: 0 1\n").is_err());
+        assert!(parse_input("0:\n##\n\n99999999x99999999: 1\n").is_err());
+        assert!(parse_input("0:\n#x\n\n4x4: 1\n").is_err());
+        assert!(parse_input("0:\n##\n\n2x2: 1\n..\n").is_err());
+        assert_eq!(
+            parse_input("0:\n##\n\n4x4 2\n").err(),
+            Some("line 4, column 4: expected \":\", found \"2\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let Ok(pictures) = render("0:\n##\n\n2x3: 2\n#..\n...\n") else {
+            panic!("the region is rendered");
+        };
+        let [Picture::Terminal(text)] = &pictures[..] else {
+            panic!("one picture per region");
+        };
+        let letters: String = text.chars().filter(|c| "#.ab".contains(*c)).collect();
+        assert_eq!(letters.matches('a').count(), 2);
+        assert_eq!(letters.matches('b').count(), 2);
+        assert_eq!(letters.len(), 6);
+        assert!(render("0:\n###\n\n3x3: 1\n").is_err());
+    }
+
+    #[test]
+    fn test_shape() {
+        let shape = Shape::parse(0, parse::lines("...\n.#.\n.##")).unwrap();
+        let text = shape.grid.map(|&c| if c { '#' } else { '.' }).to_string();
+        assert_eq!(text, "#.\n##");
+        assert_eq!(shape.area(), 3);
+    }
+
+    #[test]
+    fn test_dominoes_fill_empty_rectangles() {
+        property::check(
+            |s| (s.int(1usize..=5), s.int(1usize..=5), s.int(0usize..=13)),
+            |&(height, width, count)| {
+                let input = format!("0:\n##\n\n{height}x{width}: {count}\n");
+                part1(&input) == Ok(usize::from(2 * count <= height * width))
+            },
+        );
     }
 }
@@ -0,0 +1,282 @@
+// Mutation fuzzer: checks that a day's solver rejects malformed inputs
+// with an error instead of panicking, aborting or hanging.
+//
+// Each case runs in a child process (`fuzz-case <day>`, input on stdin) so that
+// stack overflows and infinite loops can be detected and killed as well.
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::Puzzle;
+use crate::rng::Rng;
+
+pub struct Options {
+    pub iterations: u64,
+    pub seed: u64,
+    pub timeout_secs: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            iterations: 100,
+            seed: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(1, |d| d.as_nanos() as u64),
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Values substituted to numbers of the input.
+const EXTREMES: [&str; 10] = [
+    "0",
+    "-1",
+    "1",
+    "2147483648",
+    "4294967296",
+    "-9223372036854775808",
+    "9223372036854775807",
+    "18446744073709551615",
+    "18446744073709551616",
+    "340282366920938463463374607431768211456",
+];
+
+/// Bytes that are meaningful to at least one parser.
+const INTERESTING: &[u8] = b"0123456789 \n:#.-x>()=,?OSE";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mutation {
+    ByteFlip,
+    DropLine,
+    DuplicateLine,
+    NumberExtreme,
+}
+
+const MUTATIONS: [Mutation; 4] = [
+    Mutation::ByteFlip,
+    Mutation::DropLine,
+    Mutation::DuplicateLine,
+    Mutation::NumberExtreme,
+];
+
+fn mutate(input: &mut Vec<u8>, mutation: Mutation, rng: &mut Rng) {
+    match mutation {
+        Mutation::ByteFlip => {
+            if input.is_empty() {
+                input.push(INTERESTING[rng.below(INTERESTING.len())]);
+                return;
+            }
+            let i = rng.below(input.len());
+            input[i] = if rng.next().is_multiple_of(2) {
+                INTERESTING[rng.below(INTERESTING.len())]
+            } else {
+                b' ' + rng.below(95) as u8
+            };
+        }
+        Mutation::DropLine | Mutation::DuplicateLine => {
+            let mut lines: Vec<Vec<u8>> = input
+                .split_inclusive(|&c| c == b'\n')
+                .map(<[u8]>::to_vec)
+                .collect();
+            if lines.is_empty() {
+                return;
+            }
+            let i = rng.below(lines.len());
+            if mutation == Mutation::DropLine {
+                lines.remove(i);
+            } else {
+                let mut line = lines[i].clone();
+                if !line.ends_with(b"\n") {
+                    line.push(b'\n');
+                }
+                let j = rng.below(lines.len() + 1);
+                lines.insert(j, line);
+            }
+            *input = lines.concat();
+        }
+        Mutation::NumberExtreme => {
+            let numbers = number_spans(input);
+            if numbers.is_empty() {
+                return;
+            }
+            let (start, end) = numbers[rng.below(numbers.len())];
+            let extreme = EXTREMES[rng.below(EXTREMES.len())].as_bytes();
+            input.splice(start..end, extreme.iter().copied());
+        }
+    }
+}
+
+/// Byte ranges of the (optionally negative) integers in `input`.
+fn number_spans(input: &[u8]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < input.len() {
+        if input[i].is_ascii_digit() {
+            let start = if i > 0 && input[i - 1] == b'-' {
+                i - 1
+            } else {
+                i
+            };
+            let mut end = i;
+            while end < input.len() && input[end].is_ascii_digit() {
+                end += 1;
+            }
+            spans.push((start, end));
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    spans
+}
+
+enum Outcome {
+    Ok,
+    Crash(String),
+    Hang,
+}
+
+/// Runs `fuzz-case <day>` on `input` in a child process.
+fn run_case(day: u32, input: &[u8], timeout: Duration) -> Result<Outcome, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("cannot locate executable: {e}"))?;
+
+    let mut child = Command::new(exe)
+        .arg("fuzz-case")
+        .arg(day.to_string())
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot spawn child process: {e}"))?;
+
+    // the child may die before reading everything: ignore broken pipes
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(input);
+    }
+
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            if status.success() {
+                return Ok(Outcome::Ok);
+            }
+            let mut stderr = String::new();
+            if let Some(mut pipe) = child.stderr.take() {
+                let _ = std::io::Read::read_to_string(&mut pipe, &mut stderr);
+            }
+            let reason: Vec<&str> = stderr
+                .lines()
+                .filter(|line| !line.trim().is_empty() && !line.starts_with("note:"))
+                .collect();
+            let reason = if reason.is_empty() {
+                status.to_string()
+            } else {
+                reason.join(" ")
+            };
+            return Ok(Outcome::Crash(reason));
+        }
+
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(Outcome::Hang);
+        }
+
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Fuzzes the solver of `day` starting from its real input (if any) and its example.
+/// Failing inputs are saved into `fuzz/dayNN/`. Returns the number of failures.
+pub fn fuzz(
+    day: u32,
+    puzzle: &Puzzle,
+    input: Option<&str>,
+    options: &Options,
+) -> Result<usize, String> {
+    let corpus: Vec<&str> = input.into_iter().chain([puzzle.example]).collect();
+    let timeout = Duration::from_secs(options.timeout_secs);
+    let crash_dir = format!("fuzz/day{day:02}");
+
+    println!(
+        "🐛 Fuzzing day {day}: {} iterations, seed {}",
+        options.iterations, options.seed
+    );
+
+    let mut rng = Rng::new(options.seed);
+    let mut failures = 0;
+
+    for iteration in 1..=options.iterations {
+        let mut case = corpus[rng.below(corpus.len())].as_bytes().to_vec();
+        for _ in 0..=rng.below(3) {
+            let mutation = MUTATIONS[rng.below(MUTATIONS.len())];
+            mutate(&mut case, mutation, &mut rng);
+        }
+
+        let reason = match run_case(day, &case, timeout)? {
+            Outcome::Ok => continue,
+            Outcome::Crash(reason) => reason,
+            Outcome::Hang => format!("no answer after {}s", options.timeout_secs),
+        };
+
+        failures += 1;
+
+        fs::create_dir_all(&crash_dir).map_err(|e| format!("cannot create {crash_dir}: {e}"))?;
+        let path = format!("{crash_dir}/crash-{}-{iteration}.txt", options.seed);
+        fs::write(&path, &case).map_err(|e| format!("cannot write {path}: {e}"))?;
+
+        println!("  ❌ iteration {iteration}: {reason}");
+        println!("     saved to {path}");
+    }
+
+    if failures == 0 {
+        println!("  ✅ no crash");
+    } else {
+        println!(
+            "  {failures} crashing input(s) out of {}",
+            options.iterations
+        );
+    }
+
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_spans() {
+        assert_eq!(
+            number_spans(b"(4, -6) r=31"),
+            vec![(1, 2), (4, 6), (10, 12)]
+        );
+    }
+
+    #[test]
+    fn test_mutations() {
+        let mut rng = Rng::new(42);
+
+        let mut input = b"plant 5\nspray all\n".to_vec();
+        mutate(&mut input, Mutation::DropLine, &mut rng);
+        assert!(input == b"plant 5\n" || input == b"spray all\n");
+
+        let mut input = b"plant 5\nspray all".to_vec();
+        mutate(&mut input, Mutation::DuplicateLine, &mut rng);
+        assert_eq!(input.iter().filter(|&&c| c == b'\n').count(), 2);
+
+        let mut input = b"plant 5\n".to_vec();
+        mutate(&mut input, Mutation::NumberExtreme, &mut rng);
+        let value = std::str::from_utf8(&input[6..input.len() - 1]).unwrap();
+        assert!(EXTREMES.contains(&value));
+
+        let mut input = b"plant 5\n".to_vec();
+        mutate(&mut input, Mutation::ByteFlip, &mut rng);
+        assert_eq!(input.len(), 8);
+    }
+}
@@ -0,0 +1,470 @@
+// Graph algorithms shared by the days.
+//
+// Graphs are either adjacency lists over dense node indices (`Graph`), or implicit:
+// a closure giving the successors of a node, with any hashable node type (grid
+// positions, composite ids...), for the shortest path searches.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Directed graph over the nodes `0..len`, as adjacency lists.
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    adj: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn new(len: usize) -> Self {
+        Self {
+            adj: vec![Vec::new(); len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.adj.len()
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.adj[from].push(to);
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.adj[node]
+    }
+
+    /// All edges, as (from, to) pairs.
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.adj
+            .iter()
+            .enumerate()
+            .flat_map(|(from, succ)| succ.iter().map(move |&to| (from, to)))
+    }
+}
+
+/// Distances from `start` to every reachable node, with edges of weight 0 or 1 (0-1 BFS).
+pub fn bfs01<N, I>(start: N, mut successors: impl FnMut(&N) -> I) -> HashMap<N, u64>
+where
+    N: Copy + Eq + Hash,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    let mut dist = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([(0, start)]);
+
+    while let Some((d, node)) = queue.pop_front() {
+        if d > dist[&node] {
+            continue;
+        }
+        for (next, weight) in successors(&node) {
+            assert!(weight <= 1, "0-1 BFS with an edge of weight {weight}");
+            let nd = d + weight;
+            if dist.get(&next).is_none_or(|&old| nd < old) {
+                dist.insert(next, nd);
+                if weight == 0 {
+                    queue.push_front((nd, next));
+                } else {
+                    queue.push_back((nd, next));
+                }
+            }
+        }
+    }
+
+    dist
+}
+
+/// Distances from `start` to every reachable node, with non-negative edge weights (Dijkstra).
+pub fn dijkstra<N, I>(start: N, mut successors: impl FnMut(&N) -> I) -> HashMap<N, u64>
+where
+    N: Copy + Eq + Hash + Ord,
+    I: IntoIterator<Item = (N, u64)>,
+{
+    let mut dist = HashMap::from([(start, 0)]);
+    let mut heap = BinaryHeap::from([Reverse((0, start))]);
+
+    while let Some(Reverse((d, node))) = heap.pop() {
+        if d > dist[&node] {
+            continue;
+        }
+        for (next, weight) in successors(&node) {
+            let nd = d + weight;
+            match dist.entry(next) {
+                Entry::Occupied(e) if *e.get() <= nd => continue,
+                Entry::Occupied(mut e) => *e.get_mut() = nd,
+                Entry::Vacant(e) => _ = e.insert(nd),
+            }
+            heap.push(Reverse((nd, next)));
+        }
+    }
+
+    dist
+}
+
+/// Strongly connected components (Tarjan). Returns the component of each node
+/// and the number of components, numbered in reverse topological order:
+/// an edge between two components goes to a lower number.
+///
+/// The depth-first search uses an explicit stack, so that long paths
+/// cannot overflow the call stack.
+pub fn strongly_connected_components(graph: &Graph) -> (Vec<usize>, usize) {
+    struct Tarjan<'a> {
+        graph: &'a Graph,
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        component: Vec<usize>,
+        count: usize,
+    }
+
+    impl Tarjan<'_> {
+        fn open(&mut self, node: usize) {
+            self.index[node] = Some(self.next_index);
+            self.low[node] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+        }
+
+        fn close(&mut self, at: usize) {
+            if self.index[at] == Some(self.low[at]) {
+                while let Some(node) = self.stack.pop() {
+                    self.on_stack[node] = false;
+                    self.component[node] = self.count;
+                    if node == at {
+                        break;
+                    }
+                }
+                self.count += 1;
+            }
+        }
+
+        fn visit(&mut self, root: usize) {
+            // path of the search: (node, number of its successors already seen)
+            let mut path = vec![(root, 0)];
+            self.open(root);
+
+            while let Some((at, seen)) = path.last_mut() {
+                let at = *at;
+                if let Some(&to) = self.graph.successors(at).get(*seen) {
+                    *seen += 1;
+                    match self.index[to] {
+                        None => {
+                            self.open(to);
+                            path.push((to, 0));
+                        }
+                        Some(index) if self.on_stack[to] => self.low[at] = self.low[at].min(index),
+                        Some(_) => {}
+                    }
+                } else {
+                    path.pop();
+                    if let Some(&(parent, _)) = path.last() {
+                        self.low[parent] = self.low[parent].min(self.low[at]);
+                    }
+                    self.close(at);
+                }
+            }
+        }
+    }
+
+    let n = graph.len();
+    let mut tarjan = Tarjan {
+        graph,
+        index: vec![None; n],
+        low: vec![0; n],
+        on_stack: vec![false; n],
+        stack: Vec::new(),
+        next_index: 0,
+        component: vec![0; n],
+        count: 0,
+    };
+
+    for node in 0..n {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+
+    (tarjan.component, tarjan.count)
+}
+
+/// Nodes reachable from `starts` in topological order: every edge goes to a later node.
+/// Fails with a node of a cycle if the reachable part of the graph is not acyclic.
+pub fn topological_order(
+    graph: &Graph,
+    starts: impl IntoIterator<Item = usize>,
+) -> Result<Vec<usize>, usize> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        Open,
+        Done,
+    }
+
+    let mut state = vec![State::New; graph.len()];
+    let mut order = Vec::new();
+
+    for start in starts {
+        // (node, whether its successors have been pushed)
+        let mut stack = vec![(start, false)];
+
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                state[node] = State::Done;
+                order.push(node);
+                continue;
+            }
+            match state[node] {
+                State::Done => continue,
+                State::Open => return Err(node),
+                State::New => {}
+            }
+
+            state[node] = State::Open;
+            stack.push((node, true));
+            for &next in graph.successors(node) {
+                match state[next] {
+                    State::Open => return Err(next),
+                    State::New => stack.push((next, false)),
+                    State::Done => {}
+                }
+            }
+        }
+    }
+
+    order.reverse();
+    Ok(order)
+}
+
+/// Maximum matching of a bipartite graph (Kuhn), as the left node matched with each
+/// right node of `0..right`, `adj[u]` listing the right nodes adjacent to the left node `u`.
+pub fn matching(adj: &[Vec<usize>], right: usize) -> Vec<Option<usize>> {
+    fn augment(
+        u: usize,
+        adj: &[Vec<usize>],
+        mate: &mut [Option<usize>],
+        seen: &mut [bool],
+    ) -> bool {
+        for &v in &adj[u] {
+            if seen[v] {
+                continue;
+            }
+            seen[v] = true;
+            if mate[v].is_none_or(|w| augment(w, adj, mate, seen)) {
+                mate[v] = Some(u);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut mate = vec![None; right]; // right node -> left node
+    let mut seen = vec![false; right];
+
+    for u in 0..adj.len() {
+        seen.fill(false);
+        augment(u, adj, &mut mate, &mut seen);
+    }
+    mate
+}
+
+#[derive(Clone, Debug)]
+struct FlowEdge {
+    to: usize,
+    cap: i32,
+    cost: i64,
+    rev: usize,
+}
+
+/// Flow network for min-cost flows over the nodes `0..len`.
+pub struct MinCostFlow {
+    graph: Vec<Vec<FlowEdge>>,
+}
+
+impl MinCostFlow {
+    pub fn new(len: usize) -> Self {
+        Self {
+            graph: vec![Vec::new(); len],
+        }
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, cap: i32, cost: i64) {
+        let rev_from = self.graph[to].len();
+        let rev_to = self.graph[from].len();
+        self.graph[from].push(FlowEdge {
+            to,
+            cap,
+            cost,
+            rev: rev_from,
+        });
+        self.graph[to].push(FlowEdge {
+            to: from,
+            cap: 0,
+            cost: -cost,
+            rev: rev_to,
+        });
+    }
+
+    /// Minimum cost of sending `required_flow` units from `source` to `sink`,
+    /// or `None` if the network cannot carry that much.
+    ///
+    /// Augments along shortest paths of the residual network, found with SPFA
+    /// since residual edges have negative costs.
+    pub fn solve(&mut self, source: usize, sink: usize, required_flow: i32) -> Option<i64> {
+        let n = self.graph.len();
+        let mut total_flow = 0;
+        let mut min_cost = 0;
+
+        while total_flow < required_flow {
+            let mut dist = vec![i64::MAX; n];
+            let mut parent = vec![(usize::MAX, usize::MAX); n]; // (node, edge index)
+            let mut in_queue = vec![false; n];
+            let mut queue = VecDeque::from([source]);
+
+            dist[source] = 0;
+            in_queue[source] = true;
+
+            while let Some(u) = queue.pop_front() {
+                in_queue[u] = false;
+                for (i, e) in self.graph[u].iter().enumerate() {
+                    if e.cap > 0 && dist[e.to] > dist[u].saturating_add(e.cost) {
+                        dist[e.to] = dist[u] + e.cost;
+                        parent[e.to] = (u, i);
+                        if !in_queue[e.to] {
+                            queue.push_back(e.to);
+                            in_queue[e.to] = true;
+                        }
+                    }
+                }
+            }
+
+            if dist[sink] == i64::MAX {
+                return None;
+            }
+
+            // bottleneck of the path
+            let mut flow = required_flow - total_flow;
+            let mut curr = sink;
+            while curr != source {
+                let (p, i) = parent[curr];
+                flow = flow.min(self.graph[p][i].cap);
+                curr = p;
+            }
+
+            total_flow += flow;
+            min_cost += i64::from(flow) * dist[sink];
+
+            curr = sink;
+            while curr != source {
+                let (p, i) = parent[curr];
+                self.graph[p][i].cap -= flow;
+                let rev = self.graph[p][i].rev;
+                self.graph[curr][rev].cap += flow;
+                curr = p;
+            }
+        }
+
+        Some(min_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(len: usize, edges: &[(usize, usize)]) -> Graph {
+        let mut graph = Graph::new(len);
+        for &(from, to) in edges {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_shortest_paths() {
+        // implicit graph: from n, go to n + 1 for 1 or to 2n for free
+        let successors = |&n: &u64| {
+            [(n + 1, 1), (2 * n, 0)]
+                .into_iter()
+                .filter(|&(m, _)| m <= 20)
+        };
+
+        let dist = bfs01(1, successors);
+        assert_eq!(dist[&16], 0);
+        assert_eq!(dist[&20], 1); // 1 -> 2 -> 4 -> 5 -> 10 -> 20
+        assert_eq!(dist[&19], 2); // 1 -> 2 -> 4 -> 8 -> 9 -> 18 -> 19
+        assert_eq!(dijkstra(1, successors), dist);
+
+        let weighted = |&n: &u32| {
+            [(n + 1, 1), (n + 3, 5)]
+                .into_iter()
+                .filter(|&(m, _)| m <= 9)
+        };
+        let dist = dijkstra(0, weighted);
+        assert_eq!(dist[&9], 9);
+        assert_eq!(dist.len(), 10);
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        // {0, 1, 2} -> {3, 4} -> {5}
+        let g = graph(6, &[(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 3), (4, 5)]);
+        let (component, count) = strongly_connected_components(&g);
+        assert_eq!(count, 3);
+        assert!(component[0] == component[1] && component[1] == component[2]);
+        assert_eq!(component[3], component[4]);
+        assert!(g.edges().all(|(u, v)| component[u] >= component[v]));
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let g = graph(5, &[(0, 2), (0, 1), (1, 2), (2, 3), (4, 0)]);
+        assert_eq!(topological_order(&g, [0]), Ok(vec![0, 1, 2, 3]));
+        assert_eq!(topological_order(&g, 0..5), Ok(vec![4, 0, 1, 2, 3]));
+
+        let g = graph(4, &[(0, 1), (1, 2), (2, 1), (3, 3)]);
+        assert!(topological_order(&g, [0]).is_err());
+        assert_eq!(topological_order(&g, [3]), Err(3));
+    }
+
+    #[test]
+    fn test_deep_graphs() {
+        const N: usize = 1_000_000;
+
+        // a single cycle through every node
+        let edges: Vec<_> = (0..N).map(|i| (i, (i + 1) % N)).collect();
+        let (component, count) = strongly_connected_components(&graph(N, &edges));
+        assert_eq!(count, 1);
+        assert!(component.iter().all(|&c| c == 0));
+
+        // the chain without its closing edge
+        let chain = graph(N, &edges[..N - 1]);
+        assert_eq!(strongly_connected_components(&chain).1, N);
+        assert_eq!(topological_order(&chain, [0]), Ok((0..N).collect()));
+    }
+
+    #[test]
+    fn test_matching() {
+        // left 2 can only take right 0, which forces left 0 to move to right 1
+        let adj = vec![vec![0, 1], vec![1, 2], vec![0]];
+        assert_eq!(matching(&adj, 3), vec![Some(2), Some(0), Some(1)]);
+        assert_eq!(matching(&[vec![0], vec![0]], 1), vec![Some(0)]);
+        assert_eq!(matching(&[vec![0, 1], vec![0]], 2), vec![Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn test_min_cost_flow() {
+        // two paths from 0 to 3: through 1 (cost 2) and through 2 (cost 5)
+        let mut network = MinCostFlow::new(4);
+        network.add_edge(0, 1, 1, 1);
+        network.add_edge(1, 3, 1, 1);
+        network.add_edge(0, 2, 1, 2);
+        network.add_edge(2, 3, 1, 3);
+        assert_eq!(network.solve(0, 3, 2), Some(7));
+
+        let mut network = MinCostFlow::new(2);
+        network.add_edge(0, 1, 1, 1);
+        assert_eq!(network.solve(0, 1, 2), None);
+    }
+}
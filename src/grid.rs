@@ -0,0 +1,296 @@
+// Two-dimensional grids stored row by row in a flat vector, shared by the grid-based days.
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::parse::Line;
+
+/// Position of a cell: (row, column).
+pub type Pos = (usize, usize);
+
+/// Offsets of the orthogonal neighbours.
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    rows: usize,
+    cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(rows: usize, cols: usize, mut f: impl FnMut(Pos) -> T) -> Self {
+        let cells = (0..rows)
+            .flat_map(|r| (0..cols).map(move |c| (r, c)))
+            .map(&mut f)
+            .collect();
+        Self { rows, cols, cells }
+    }
+
+    /// Parses rectangular lines of text, `cell` giving the value of each character
+    /// or `None` if the character is not allowed.
+    pub fn parse<'a>(
+        lines: impl IntoIterator<Item = Line<'a>>,
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, String> {
+        let mut cells = Vec::new();
+        let mut rows = 0;
+        let mut cols = 0;
+
+        for mut line in lines {
+            let before = cells.len();
+            while let Some(ch) = line.peek() {
+                let value =
+                    cell(ch).ok_or_else(|| line.error(format!("unexpected character {ch:?}")))?;
+                cells.push(value);
+                line.char()?;
+            }
+
+            let width = cells.len() - before;
+            if rows == 0 {
+                cols = width;
+            } else if width != cols {
+                return Err(line.error(format!(
+                    "row of {width} cells instead of {cols}: the grid is not rectangular"
+                )));
+            }
+            rows += 1;
+        }
+
+        if cells.is_empty() {
+            return Err("empty grid".to_string());
+        }
+
+        Ok(Self { rows, cols, cells })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Index of `pos` in the row by row order, from 0 to `rows * cols - 1`.
+    pub fn index_of(&self, (r, c): Pos) -> usize {
+        assert!(
+            r < self.rows && c < self.cols,
+            "({r}, {c}) is out of the grid"
+        );
+        r * self.cols + c
+    }
+
+    /// Positions of the cells, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + use<T> {
+        let cols = self.cols;
+        (0..self.rows * cols).map(move |i| (i / cols, i % cols))
+    }
+
+    /// Cells with their positions, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// Position of the first cell, row by row, satisfying `predicate`.
+    pub fn find(&self, predicate: impl Fn(&T) -> bool) -> Option<Pos> {
+        self.iter()
+            .find(|(_, cell)| predicate(cell))
+            .map(|(pos, _)| pos)
+    }
+
+    fn offset(&self, (r, c): Pos, (dr, dc): (isize, isize)) -> Option<Pos> {
+        let r = r.checked_add_signed(dr).filter(|&r| r < self.rows)?;
+        let c = c.checked_add_signed(dc).filter(|&c| c < self.cols)?;
+        Some((r, c))
+    }
+
+    /// Positions of the up to 4 orthogonal neighbours of `pos` inside the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ORTHOGONAL.iter().filter_map(move |&d| self.offset(pos, d))
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Rectangle of `rows` × `cols` cells whose top-left corner is `top_left`.
+    pub fn view(&self, top_left: Pos, rows: usize, cols: usize) -> View<'_, T> {
+        assert!(
+            top_left.0 + rows <= self.rows && top_left.1 + cols <= self.cols,
+            "view out of the grid"
+        );
+        View {
+            grid: self,
+            top_left,
+            rows,
+            cols,
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(rows: usize, cols: usize, value: T) -> Self {
+        Self {
+            rows,
+            cols,
+            cells: vec![value; rows * cols],
+        }
+    }
+
+    /// Grid rotated a quarter turn clockwise.
+    pub fn rotate(&self) -> Self {
+        Self::from_fn(self.cols, self.rows, |(r, c)| {
+            self[(self.rows - 1 - c, r)].clone()
+        })
+    }
+
+    /// Grid mirrored left to right.
+    pub fn flip_horizontal(&self) -> Self {
+        Self::from_fn(self.rows, self.cols, |(r, c)| {
+            self[(r, self.cols - 1 - c)].clone()
+        })
+    }
+
+    /// Grid mirrored top to bottom.
+    pub fn flip_vertical(&self) -> Self {
+        Self::from_fn(self.rows, self.cols, |(r, c)| {
+            self[(self.rows - 1 - r, c)].clone()
+        })
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        &self.cells[self.index_of(pos)]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let i = self.index_of(pos);
+        &mut self.cells[i]
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.cells.chunks(self.cols.max(1)).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Shows the grid row by row, which reads better than the flat storage.
+impl<T: fmt::Debug> fmt::Debug for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.cells.chunks(self.cols.max(1)))
+            .finish()
+    }
+}
+
+/// Rectangular part of a grid, borrowed.
+pub struct View<'a, T> {
+    grid: &'a Grid<T>,
+    top_left: Pos,
+    rows: usize,
+    cols: usize,
+}
+
+impl<T> View<'_, T> {
+    /// Positions of the cells in the underlying grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + use<T> {
+        let (top, left) = self.top_left;
+        let cols = self.cols;
+        (0..self.rows * cols).map(move |i| (top + i / cols, left + i % cols))
+    }
+
+    /// Cells with their positions in the underlying grid, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().map(|pos| (pos, &self.grid[pos]))
+    }
+}
+
+impl<T: Clone> View<'_, T> {
+    /// Copies the cells into a grid of their own.
+    pub fn to_grid(&self) -> Grid<T> {
+        let (top, left) = self.top_left;
+        Grid::from_fn(self.rows, self.cols, |(r, c)| {
+            self.grid[(top + r, left + c)].clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn letters() -> Grid<char> {
+        Grid::parse(parse::lines("abc\ndef"), Some).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let digit = |c: char| c.to_digit(10);
+        let grid = Grid::parse(parse::lines("12\n34\n"), digit).unwrap();
+        assert_eq!((grid.rows(), grid.cols()), (2, 2));
+        assert_eq!(grid[(1, 0)], 3);
+        assert_eq!(grid.index_of((1, 0)), 2);
+        assert_eq!(grid.find(|&d| d > 1), Some((0, 1)));
+
+        assert_eq!(
+            Grid::parse(parse::lines("12\n3x"), digit),
+            Err("line 2, column 2: unexpected character 'x'".to_string())
+        );
+        assert_eq!(
+            Grid::parse(parse::lines("12\n345"), digit),
+            Err(
+                "line 2, column 4: row of 3 cells instead of 2: the grid is not rectangular"
+                    .to_string()
+            )
+        );
+        assert!(Grid::parse(parse::lines(""), digit).is_err());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = letters();
+        let around = |pos| -> String { grid.neighbours4(pos).map(|p| grid[p]).collect() };
+        assert_eq!(around((0, 0)), "bd");
+        assert_eq!(around((1, 1)), "bfd");
+    }
+
+    #[test]
+    fn test_transforms() {
+        let grid = letters();
+        assert_eq!(grid.rotate().to_string(), "da\neb\nfc");
+        assert_eq!(grid.rotate().rotate().rotate().rotate(), grid);
+        assert_eq!(grid.flip_horizontal().to_string(), "cba\nfed");
+        assert_eq!(grid.flip_vertical().to_string(), "def\nabc");
+        assert_eq!(grid.map(|c| c.to_ascii_uppercase()).to_string(), "ABC\nDEF");
+    }
+
+    #[test]
+    fn test_view() {
+        let grid = letters();
+        let view = grid.view((0, 1), 2, 1);
+        assert_eq!(view.positions().collect::<Vec<_>>(), [(0, 1), (1, 1)]);
+        assert_eq!(view.iter().map(|(_, &c)| c).collect::<String>(), "be");
+        assert_eq!(view.to_grid().to_string(), "b\ne");
+        assert_eq!(format!("{:?}", view.to_grid()), "[['b'], ['e']]");
+    }
+}
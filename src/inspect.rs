@@ -0,0 +1,184 @@
+// Inspection of an input before solving it: statistics, and the assumptions of the
+// solvers that the input breaks, each with an explanation.
+
+use std::fmt;
+
+use crate::parse;
+
+pub struct Report {
+    /// (name, value), in the order of insertion
+    stats: Vec<(String, String)>,
+    violations: Vec<String>,
+}
+
+impl Report {
+    /// Report with the statistics shared by all the inputs: the blocks of at least
+    /// 2 rows of the same width without blanks are grids, the integers of the other
+    /// blocks are numbers.
+    pub fn new(input: &str) -> Self {
+        let mut report = Self {
+            stats: Vec::new(),
+            violations: Vec::new(),
+        };
+        let blocks = parse::blocks(input);
+        report.stat("lines", parse::lines(input).count());
+        report.stat("blocks", blocks.len());
+
+        let (grids, others): (Vec<_>, Vec<_>) = blocks
+            .iter()
+            .map(|block| (block, grid_size(block)))
+            .partition(|(_, size)| size.is_some());
+        let numbers: Vec<&str> = others
+            .iter()
+            .flat_map(|(block, _)| block.lines())
+            .flat_map(|mut line| numbers(line.rest()))
+            .collect();
+        if !numbers.is_empty() {
+            report.stat("numbers", numbers.len());
+            // the ones too long for an i128 are left out of the range
+            report.stat(
+                "number range",
+                range(numbers.iter().filter_map(|n| n.parse::<i128>().ok())),
+            );
+        }
+        if !grids.is_empty() {
+            let mut sizes: Vec<String> = grids
+                .iter()
+                .filter_map(|&(_, size)| size)
+                .map(|(rows, cols)| format!("{rows}x{cols}"))
+                .collect();
+            sizes.sort();
+            sizes.dedup();
+            report.stat("grids", grids.len());
+            report.stat("grid sizes", sizes.join(", "));
+        }
+        report
+    }
+
+    pub fn stat(&mut self, name: &str, value: impl fmt::Display) {
+        self.stats.push((name.to_string(), value.to_string()));
+    }
+
+    /// Records a broken assumption, explaining what the solver expects.
+    pub fn violation(&mut self, explanation: impl Into<String>) {
+        self.violations.push(explanation.into());
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Rows and columns of a block of at least 2 rows and 2 columns, all of the same
+/// width and without blanks.
+fn grid_size(block: &parse::Block) -> Option<(usize, usize)> {
+    let mut widths = block.lines().map(|mut line| {
+        let row = line.rest();
+        (!row.contains(char::is_whitespace)).then(|| row.chars().count())
+    });
+    let cols = widths.next()??;
+    let mut rows = 1;
+    for width in widths {
+        (width? == cols).then_some(())?;
+        rows += 1;
+    }
+    (rows >= 2 && cols >= 2).then_some((rows, cols))
+}
+
+/// Decimal integers of `text`, with their minus sign unless it follows a letter or
+/// a digit (as in `a-1`).
+fn numbers(text: &str) -> impl Iterator<Item = &str> {
+    let bytes = text.as_bytes();
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        let start = at + bytes[at..].iter().position(u8::is_ascii_digit)?;
+        let end = start
+            + bytes[start..]
+                .iter()
+                .position(|b| !b.is_ascii_digit())
+                .unwrap_or(bytes.len() - start);
+        at = end;
+        let signed = start > 0
+            && bytes[start - 1] == b'-'
+            && (start < 2 || !bytes[start - 2].is_ascii_alphanumeric());
+        Some(&text[start - usize::from(signed)..end])
+    })
+}
+
+/// Smallest and largest of `values`, as "min to max".
+pub fn range<T: Ord + Copy + fmt::Display>(values: impl IntoIterator<Item = T>) -> String {
+    let mut values = values.into_iter();
+    let Some(first) = values.next() else {
+        return "none".to_string();
+    };
+    let (min, max) = values.fold((first, first), |(min, max), v| (min.min(v), max.max(v)));
+    if min == max {
+        min.to_string()
+    } else {
+        format!("{min} to {max}")
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.stats.iter().map(|(name, _)| name.len()).max();
+        for (name, value) in &self.stats {
+            writeln!(f, "  {name:<width$}  {value}", width = width.unwrap_or(0))?;
+        }
+        if self.violations.is_empty() {
+            writeln!(f, "  ✅ no violated assumption")?;
+        }
+        for violation in &self.violations {
+            writeln!(f, "  ❌ {violation}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        assert_eq!(range([3, 1, 2]), "1 to 3");
+        assert_eq!(range([2, 2]), "2");
+        assert_eq!(range([5, 9, 1, 7]), "1 to 9");
+        assert_eq!(range(Vec::<u32>::new()), "none");
+    }
+
+    #[test]
+    fn test_numbers() {
+        let found: Vec<&str> = numbers("p=-3,14 a-1 x12y 007").collect();
+        assert_eq!(found, ["-3", "14", "1", "12", "007"]);
+        assert_eq!(numbers("none").count(), 0);
+    }
+
+    #[test]
+    fn test_generic_stats() {
+        let report = Report::new("#.#\n.O.\n\nsize 5x-7: -2 40\n\n..\n..\n..\n");
+        assert_eq!(
+            report.to_string(),
+            "  lines         6\n  blocks        3\n  numbers       4\n  number range  -2 to 40\n  \
+             grids         2\n  grid sizes    2x3, 3x2\n  ✅ no violated assumption\n"
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = Report::new("a\n\nb\nc\n");
+        assert!(report.is_valid());
+        assert_eq!(
+            report.to_string(),
+            "  lines   3\n  blocks  2\n  ✅ no violated assumption\n"
+        );
+
+        report.violation("too many b");
+        assert!(!report.is_valid());
+        assert!(
+            report
+                .to_string()
+                .ends_with("  blocks  2\n  ❌ too many b\n")
+        );
+    }
+}
@@ -1,3 +1,5 @@
+mod batch;
+mod bigint;
 mod day13;
 mod day14;
 mod day15;
@@ -11,8 +13,24 @@ mod day22;
 mod day23;
 mod day24;
 mod day25;
+mod fuzz;
+mod graph;
+mod grid;
+mod inspect;
+mod modint;
+mod parse;
+#[cfg(test)]
+mod property;
+mod render;
+mod rng;
+mod scaffold;
+mod serve;
+mod trace;
+mod watch;
 
-const SOLUTIONS: [(u32, &str, &str); 13] = [
+use bigint::BigUint;
+
+const SOLUTIONS: &[(u32, &str, &str)] = &[
     (13, "87485764037410", "1019690398768"),
     (14, "75292", "824149222686720"),
     (15, "82548913169553421", "181256043161563369"),
@@ -28,56 +46,499 @@ const SOLUTIONS: [(u32, &str, &str); 13] = [
     (25, "875", "492"),
 ];
 
+/// Days of the event.
+const DAYS: std::ops::RangeInclusive<u32> = 1..=25;
+
+/// Days of the event whose puzzles are not supported.
+const UNSUPPORTED: std::ops::RangeInclusive<u32> = 1..=12;
+
 fn main() {
     let mut args = std::env::args();
     let _ = args.next();
 
-    if let Some(day) = args.next() {
-        solve(day);
+    let mut day = None;
+    let mut watch = false;
+    let mut render = false;
+    let mut exact = false;
+    let mut modulus = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "new" if day.is_none() => return new_day(args),
+            "fuzz" if day.is_none() => return fuzz_day(args),
+            "fuzz-case" if day.is_none() => return fuzz_case(args),
+            "inspect" if day.is_none() => return inspect_day(args),
+            "run" if day.is_none() => return run_batch(args),
+            "serve" if day.is_none() => return serve(args),
+            "--watch" => watch = true,
+            "--render" => render = true,
+            "--exact" => exact = true,
+            "--modulus" => match args.next().and_then(|m| m.parse::<BigUint>().ok()) {
+                Some(m) if !m.is_zero() => modulus = Some(m),
+                _ => {
+                    eprintln!("error: --modulus expects a positive integer");
+                    std::process::exit(2);
+                }
+            },
+            "--explain" => trace::set_level(Some(trace::Level::Info)),
+            _ => match arg.strip_prefix("--explain=") {
+                Some(level) => match level.parse() {
+                    Ok(level) => trace::set_level(Some(level)),
+                    Err(e) => {
+                        eprintln!("error: {e}");
+                        std::process::exit(2);
+                    }
+                },
+                None => day = Some(arg),
+            },
+        }
+    }
+
+    if let Some(day) = day {
+        let run = || {
+            let ok = solve(&day)?;
+            if render {
+                render_day(&day)?;
+            }
+            if exact || modulus.is_some() {
+                count_day(&day, exact, modulus.as_ref())?;
+            }
+            Ok(ok)
+        };
+        if watch {
+            watch_day(&day, run);
+        }
+        match run() {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else if watch || render || exact || modulus.is_some() {
+        eprintln!("usage: <day> [--watch] [--render] [--exact] [--modulus M] [--explain[=LEVEL]]");
+        std::process::exit(2);
     } else {
-        for day in 13..=25 {
-            solve(day.to_string());
+        let mut success = true;
+        for day in DAYS.filter(|&day| puzzle(day).is_some()) {
+            match solve(&day.to_string()) {
+                Ok(ok) => success &= ok,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    success = false;
+                }
+            }
             println!();
         }
+        if !success {
+            std::process::exit(1);
+        }
     }
 }
 
-fn solve(day: String) {
-    println!("🎁 Day {}: ", day);
+fn input_path(day: u32) -> String {
+    format!("inputs/day{day:02}.txt")
+}
 
-    let answer = match day.as_str() {
-        "13" => crate::day13::solve(),
-        "14" => crate::day14::solve(),
-        "15" => crate::day15::solve(),
-        "16" => crate::day16::solve(),
-        "17" => crate::day17::solve(),
-        "18" => crate::day18::solve(),
-        "19" => crate::day19::solve(),
-        "20" => crate::day20::solve(),
-        "21" => crate::day21::solve(),
-        "25" => crate::day25::solve(),
-        "22" => crate::day22::solve(),
-        "23" => crate::day23::solve(),
-        "24" => crate::day24::solve(),
-        _ => None,
+/// Calls `run`, which solves a day and prints what the options ask for, again each time
+/// the input of the day changes.
+fn watch_day(day: &str, run: impl Fn() -> Result<bool, String>) -> ! {
+    let Some(day) = day.parse().ok().filter(|&day| puzzle(day).is_some()) else {
+        eprintln!("error: no solver for day '{day}'");
+        std::process::exit(1);
     };
 
-    if let Some((p1, p2)) = answer {
-        if let Some((_, expected_p1, expected_p2)) =
-            SOLUTIONS.iter().find(|(d, _, _)| d.to_string() == day)
-        {
-            if p1 == *expected_p1 {
-                println!("  Part 1: {p1} ✅");
-            } else {
-                println!("  Part 1: {p1} ❌ (expected {})", expected_p1);
+    watch::watch(&[input_path(day)], || {
+        if let Err(e) = run() {
+            eprintln!("error: {e}");
+        }
+    })
+}
+
+fn new_day(mut args: impl Iterator<Item = String>) {
+    let mut day = None;
+    let mut title = None;
+
+    while let Some(arg) = args.next() {
+        if arg == "--title" {
+            title = args.next();
+        } else {
+            day = arg.parse::<u32>().ok();
+        }
+    }
+
+    let (Some(day), Some(title)) = (day, title) else {
+        eprintln!("usage: new <day> --title \"...\"");
+        std::process::exit(2);
+    };
+
+    if let Err(e) = scaffold::new_day(day, &title) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn fuzz_day(mut args: impl Iterator<Item = String>) {
+    let mut day = None;
+    let mut options = fuzz::Options::default();
+
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--iterations" => &mut options.iterations,
+            "--seed" => &mut options.seed,
+            "--timeout" => &mut options.timeout_secs,
+            _ => {
+                day = arg.parse::<u32>().ok();
+                continue;
             }
-            if p2 == *expected_p2 {
-                println!("  Part 2: {p2} ✅");
-            } else {
-                println!("  Part 2: {p2} ❌ (expected {})", expected_p2);
+        };
+        match args.next().and_then(|v| v.parse().ok()) {
+            Some(v) => *value = v,
+            None => day = None,
+        }
+    }
+
+    let Some((day, puzzle)) = day.and_then(|day| Some((day, puzzle(day)?))) else {
+        eprintln!("usage: fuzz <day> [--iterations N] [--seed S] [--timeout SECS]");
+        std::process::exit(2);
+    };
+
+    let input = std::fs::read_to_string(input_path(day)).ok();
+
+    match fuzz::fuzz(day, &puzzle, input.as_deref(), &options) {
+        Ok(0) => {}
+        Ok(_) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_batch(mut args: impl Iterator<Item = String>) {
+    let (Some("--inputs-dir"), Some(dir), None) =
+        (args.next().as_deref(), args.next(), args.next())
+    else {
+        eprintln!("usage: run --inputs-dir <dir>");
+        std::process::exit(2);
+    };
+
+    match batch::run(std::path::Path::new(&dir)) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn serve(mut args: impl Iterator<Item = String>) {
+    let (Some("--port"), Some(Ok(port)), None) = (
+        args.next().as_deref(),
+        args.next().map(|port| port.parse()),
+        args.next(),
+    ) else {
+        eprintln!("usage: serve --port P");
+        std::process::exit(2);
+    };
+
+    if let Err(e) = serve::serve(port) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn inspect_day(mut args: impl Iterator<Item = String>) {
+    let Some((day, puzzle)) = args
+        .next()
+        .and_then(|day| day.parse().ok())
+        .and_then(|day| Some((day, puzzle(day)?)))
+    else {
+        eprintln!("usage: inspect <day>");
+        std::process::exit(2);
+    };
+
+    let Ok(input) = std::fs::read_to_string(input_path(day)) else {
+        eprintln!("error: input not found for day {day}");
+        std::process::exit(1);
+    };
+
+    println!("🔎 Day {day}: ");
+    let mut report = inspect::Report::new(&input);
+    match puzzle.inspect {
+        Some(inspect) => {
+            if let Err(e) = inspect(&input, &mut report) {
+                report.violation(format!("the input cannot be parsed: {e}"));
             }
         }
+        None => report.stat("assumptions", "not checked for this day"),
+    }
+    print!("{report}");
+
+    if !report.is_valid() {
+        std::process::exit(1);
+    }
+}
+
+/// Internal: solves the input read from stdin, used by `fuzz` to isolate each case.
+fn fuzz_case(mut args: impl Iterator<Item = String>) {
+    let Some(puzzle) = args.next().and_then(|day| puzzle(day.parse().ok()?)) else {
+        std::process::exit(2);
+    };
+    // stderr is the crash report
+    trace::set_level(None);
+
+    let mut input = String::new();
+    if std::io::Read::read_to_string(&mut std::io::stdin(), &mut input).is_err() {
+        std::process::exit(2);
+    }
+
+    match (puzzle.solve)(&input) {
+        Ok((p1, p2)) => println!("{p1}\n{p2}"),
+        Err(e) => println!("error: {e}"),
+    }
+}
+
+/// Draws the solution of a day for its input.
+type Render = fn(&str) -> Result<Vec<render::Picture>, String>;
+
+/// Adds the statistics and the broken assumptions of an input to a report.
+type Inspect = fn(&str, &mut inspect::Report) -> Result<(), String>;
+
+/// Answer of part 2 before it is reduced by the modulus of the puzzle.
+type Exact = fn(&str) -> Result<BigUint, String>;
+
+/// Answers of both parts, with those to the queries that an input asks besides the
+/// puzzle, one per line.
+type Queries = fn(&str) -> Result<((String, String), Vec<String>), String>;
+
+/// Entry points of a day.
+struct Puzzle {
+    /// Solves both parts for the given input.
+    solve: fn(&str) -> Result<(String, String), String>,
+    /// Example input of the puzzle statement.
+    example: &'static str,
+    /// Draws the solution, for the days that can.
+    render: Option<Render>,
+    /// Checks an input against the assumptions of the solver, for the days that make some.
+    inspect: Option<Inspect>,
+    /// Counts part 2 exactly, for the days whose answer is reduced by a modulus.
+    exact: Option<Exact>,
+    /// Solves both parts and answers the queries of an input in one go, for the days whose
+    /// inputs may ask some.
+    queries: Option<Queries>,
+}
+
+impl Puzzle {
+    const fn new(
+        solve: fn(&str) -> Result<(String, String), String>,
+        example: &'static str,
+    ) -> Self {
+        Self {
+            solve,
+            example,
+            render: None,
+            inspect: None,
+            exact: None,
+            queries: None,
+        }
+    }
+
+    const fn with_render(self, render: Render) -> Self {
+        Self {
+            render: Some(render),
+            ..self
+        }
+    }
+
+    const fn with_inspect(self, inspect: Inspect) -> Self {
+        Self {
+            inspect: Some(inspect),
+            ..self
+        }
+    }
+
+    const fn with_exact(self, exact: Exact) -> Self {
+        Self {
+            exact: Some(exact),
+            ..self
+        }
+    }
+
+    const fn with_queries(self, queries: Queries) -> Self {
+        Self {
+            queries: Some(queries),
+            ..self
+        }
+    }
+
+    /// Answers of both parts for an input, with those to its queries, none for the days
+    /// that take none.
+    fn solve_with_queries(&self, input: &str) -> Result<((String, String), Vec<String>), String> {
+        match self.queries {
+            Some(queries) => queries(input),
+            None => Ok(((self.solve)(input)?, Vec::new())),
+        }
+    }
+}
+
+fn puzzle(day: u32) -> Option<Puzzle> {
+    match day {
+        13 => {
+            Some(Puzzle::new(day13::solve, day13::EXAMPLE).with_queries(day13::solve_with_queries))
+        }
+        14 => Some(
+            Puzzle::new(day14::solve, day14::EXAMPLE)
+                .with_render(day14::render)
+                .with_inspect(day14::inspect)
+                .with_exact(day14::exact),
+        ),
+        15 => Some(Puzzle::new(day15::solve, day15::EXAMPLE)),
+        16 => Some(Puzzle::new(day16::solve, day16::EXAMPLE)),
+        17 => Some(Puzzle::new(day17::solve, day17::EXAMPLE)),
+        18 => Some(Puzzle::new(day18::solve, day18::EXAMPLE)),
+        19 => Some(Puzzle::new(day19::solve, day19::EXAMPLE).with_render(day19::render)),
+        20 => Some(Puzzle::new(day20::solve, day20::EXAMPLE).with_render(day20::render)),
+        21 => Some(
+            Puzzle::new(day21::solve, day21::EXAMPLE)
+                .with_render(day21::render)
+                .with_inspect(day21::inspect),
+        ),
+        22 => Some(Puzzle::new(day22::solve, day22::EXAMPLE)),
+        23 => Some(Puzzle::new(day23::solve, day23::EXAMPLE).with_render(day23::render)),
+        24 => Some(Puzzle::new(day24::solve, day24::EXAMPLE)),
+        25 => Some(
+            Puzzle::new(day25::solve, day25::EXAMPLE)
+                .with_render(day25::render)
+                .with_inspect(day25::inspect),
+        ),
+        _ => None,
+    }
+}
+
+/// Solves a day and checks its answers against `SOLUTIONS`.
+/// Returns `false` if an answer differs from the expected one.
+fn solve(day: &str) -> Result<bool, String> {
+    let Some(day) = day.parse().ok().filter(|day| DAYS.contains(day)) else {
+        return Err(format!("unknown day '{day}'"));
+    };
+
+    let Some(puzzle) = puzzle(day) else {
+        return Err(no_solver(day));
+    };
+
+    let Ok(input) = std::fs::read_to_string(input_path(day)) else {
+        return Err(format!("input not found for day {day}"));
+    };
+
+    println!("🎁 Day {day}: ");
+
+    let _scope = trace::scope(day);
+    let ((p1, p2), queries) = puzzle
+        .solve_with_queries(&input)
+        .map_err(|e| format!("day {day}: {e}"))?;
+    for answer in queries {
+        println!("  {answer}");
+    }
+
+    let (expected_p1, expected_p2) = expected(day);
+    let ok1 = check(1, &p1, expected_p1);
+    let ok2 = check(2, &p2, expected_p2);
+    Ok(ok1 && ok2)
+}
+
+/// Prints the pictures of a day already solved: the terminal ones on stdout,
+/// the documents in `renders/dayNN-K.svg` or `renders/dayNN-K.dot`.
+fn render_day(day: &str) -> Result<(), String> {
+    let day: u32 = day.parse().map_err(|_| format!("unknown day '{day}'"))?;
+    let render = puzzle(day)
+        .and_then(|puzzle| puzzle.render)
+        .ok_or_else(|| format!("no rendering for day {day}"))?;
+    let input = std::fs::read_to_string(input_path(day))
+        .map_err(|_| format!("input not found for day {day}"))?;
+
+    let _scope = trace::scope(day);
+    let pictures = render(&input).map_err(|e| format!("day {day}: {e}"))?;
+    for (k, picture) in (1..).zip(pictures) {
+        let (extension, document) = match picture {
+            render::Picture::Terminal(text) => {
+                print!("\n{text}");
+                continue;
+            }
+            render::Picture::Svg(svg) => ("svg", svg.to_string()),
+            render::Picture::Dot(dot) => ("dot", dot),
+        };
+        let path = format!("renders/day{day:02}-{k}.{extension}");
+        std::fs::create_dir_all("renders")
+            .and_then(|()| std::fs::write(&path, document))
+            .map_err(|e| format!("cannot write {path}: {e}"))?;
+        println!("  Rendered {path}");
+    }
+    Ok(())
+}
+
+/// Prints part 2 of a day already solved as counted exactly: abbreviated if `exact`,
+/// and reduced by `modulus` if any.
+fn count_day(day: &str, exact: bool, modulus: Option<&BigUint>) -> Result<(), String> {
+    let day: u32 = day.parse().map_err(|_| format!("unknown day '{day}'"))?;
+    let count = puzzle(day)
+        .and_then(|puzzle| puzzle.exact)
+        .ok_or_else(|| format!("no exact count for day {day}"))?;
+    let input = std::fs::read_to_string(input_path(day))
+        .map_err(|_| format!("input not found for day {day}"))?;
+
+    let _scope = trace::scope(day);
+    let count = count(&input).map_err(|e| format!("day {day}: {e}"))?;
+    if exact {
+        println!("  Part 2 exactly: {}", count.abbreviated());
+    }
+    if let Some(modulus) = modulus {
+        println!("  Part 2 mod {modulus}: {}", &count % modulus);
+    }
+    Ok(())
+}
+
+/// Error for a day of the event without a solver.
+fn no_solver(day: u32) -> String {
+    if UNSUPPORTED.contains(&day) {
+        format!("no solver for day {day}: days 1 to 12 are not supported")
+    } else {
+        format!("no solver for day {day} yet")
+    }
+}
+
+/// Known answers of a day for the input of `inputs/`, empty if unknown.
+fn expected(day: u32) -> (&'static str, &'static str) {
+    SOLUTIONS
+        .iter()
+        .find(|(d, _, _)| *d == day)
+        .map_or(("", ""), |&(_, p1, p2)| (p1, p2))
+}
+
+/// Prints the answer of a part. Returns `false` if it differs from a known `expected` answer.
+fn check(part: u32, answer: &str, expected: &str) -> bool {
+    if expected.is_empty() {
+        println!("  Part {part}: {answer}");
+    } else if answer == expected {
+        println!("  Part {part}: {answer} ✅");
     } else {
-        println!("unknown day '{day}' or input not found");
+        println!("  Part {part}: {answer} ❌ (expected {expected})");
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solutions_have_a_puzzle() {
+        for &(day, _, _) in SOLUTIONS {
+            assert!(puzzle(day).is_some(), "no solver for day {day}");
+        }
+        assert!(SOLUTIONS.is_sorted_by_key(|&(day, _, _)| day));
     }
 }
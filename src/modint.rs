@@ -0,0 +1,261 @@
+// Modular arithmetic on u128 for any modulus up to 2^127.
+//
+// Products are computed on 256 bits. A modulus m = 2^k * q (q odd) is handled as
+// two parts recombined with the Chinese remainder theorem: modulo 2^k with wrapping
+// arithmetic, and modulo q in Montgomery form, which reduces a product with two
+// multiplications instead of a division.
+
+/// Largest modulus supported: sums of two residues must fit in a u128.
+pub const MAX_MODULUS: u128 = 1 << 127;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Modulus {
+    m: u128,
+    /// m = 2^shift * odd
+    shift: u32,
+    odd: Montgomery,
+    /// inverse of `odd` modulo 2^shift
+    odd_inv: u128,
+}
+
+/// Constants of the Montgomery form modulo an odd number, with R = 2^128.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Montgomery {
+    q: u128,
+    /// -q^-1 mod R
+    neg_inv: u128,
+    /// R^2 mod q
+    r2: u128,
+}
+
+/// Full product of `a` and `b`, as (high, low) halves.
+pub fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const LOW: u128 = u64::MAX as u128;
+
+    let (a1, a0) = (a >> 64, a & LOW);
+    let (b1, b0) = (b >> 64, b & LOW);
+
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+
+    let mid = (p00 >> 64) + (p01 & LOW) + (p10 & LOW);
+    let low = (p00 & LOW) | (mid << 64);
+    let high = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    (high, low)
+}
+
+impl Montgomery {
+    fn new(q: u128) -> Self {
+        // Newton's iteration doubles the number of correct low bits: 3 -> 6 -> ... -> 192
+        let mut inv = q;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u128.wrapping_sub(q.wrapping_mul(inv)));
+        }
+
+        // R mod q, then doubled 128 times
+        let mut r2 = (u128::MAX % q + 1) % q;
+        for _ in 0..128 {
+            r2 <<= 1;
+            if r2 >= q {
+                r2 -= q;
+            }
+        }
+
+        Self {
+            q,
+            neg_inv: inv.wrapping_neg(),
+            r2,
+        }
+    }
+
+    /// t / R mod q for t = (high, low) < q * R.
+    fn reduce(&self, (high, low): (u128, u128)) -> u128 {
+        let u = low.wrapping_mul(self.neg_inv);
+        let (uh, ul) = mul_wide(u, self.q);
+        // low + ul is a multiple of R: it only carries
+        let carry = u128::from(low.overflowing_add(ul).1);
+        let t = high + uh + carry;
+        if t >= self.q { t - self.q } else { t }
+    }
+
+    fn mul(&self, a: u128, b: u128) -> u128 {
+        self.reduce(mul_wide(a, b))
+    }
+
+    fn enter(&self, a: u128) -> u128 {
+        let a = if a < self.q { a } else { a % self.q };
+        self.mul(a, self.r2)
+    }
+
+    fn leave(&self, a: u128) -> u128 {
+        self.reduce((0, a))
+    }
+
+    fn pow(&self, base: u128, mut exp: u64) -> u128 {
+        let mut result = self.enter(1);
+        let mut base = self.enter(base);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        self.leave(result)
+    }
+}
+
+impl Modulus {
+    /// Panics if `m` is 0 or greater than `MAX_MODULUS`.
+    pub fn new(m: u128) -> Self {
+        assert!(
+            m > 0 && m <= MAX_MODULUS,
+            "unsupported modulus {m} (1 to 2^127)"
+        );
+
+        let shift = m.trailing_zeros();
+        let q = m >> shift;
+
+        // the inverse of q modulo R, truncated
+        let mut odd_inv = q;
+        for _ in 0..6 {
+            odd_inv = odd_inv.wrapping_mul(2u128.wrapping_sub(q.wrapping_mul(odd_inv)));
+        }
+
+        Self {
+            m,
+            shift,
+            odd: Montgomery::new(q),
+            odd_inv: odd_inv & Self::mask(shift),
+        }
+    }
+
+    fn mask(shift: u32) -> u128 {
+        if shift == 128 {
+            u128::MAX
+        } else {
+            (1 << shift) - 1
+        }
+    }
+
+    /// Residue of `a`.
+    pub fn reduce(&self, a: u128) -> u128 {
+        if a < self.m { a } else { a % self.m }
+    }
+
+    /// The residue modulo m given its residues modulo the odd part and modulo 2^shift.
+    fn combine(&self, odd: u128, pow2: u128) -> u128 {
+        let mask = Self::mask(self.shift);
+        let t = (pow2.wrapping_sub(odd) & mask).wrapping_mul(self.odd_inv) & mask;
+        odd + self.odd.q * t
+    }
+
+    pub fn add(&self, a: u128, b: u128) -> u128 {
+        let s = self.reduce(a) + self.reduce(b);
+        if s >= self.m { s - self.m } else { s }
+    }
+
+    pub fn sub(&self, a: u128, b: u128) -> u128 {
+        let (a, b) = (self.reduce(a), self.reduce(b));
+        if a >= b { a - b } else { a + (self.m - b) }
+    }
+
+    pub fn mul(&self, a: u128, b: u128) -> u128 {
+        if let Some(product) = a.checked_mul(b) {
+            return self.reduce(product);
+        }
+        let odd = self
+            .odd
+            .leave(self.odd.mul(self.odd.enter(a), self.odd.enter(b)));
+        let pow2 = a.wrapping_mul(b);
+        self.combine(odd, pow2 & Self::mask(self.shift))
+    }
+
+    pub fn pow(&self, base: u128, exp: u64) -> u128 {
+        let odd = self.odd.pow(base, exp);
+        let pow2 = pow_wrapping(base, exp);
+        self.combine(odd, pow2 & Self::mask(self.shift))
+    }
+}
+
+fn pow_wrapping(mut base: u128, mut exp: u64) -> u128 {
+    let mut result: u128 = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property;
+
+    /// Reference product: double-and-add, one bit at a time.
+    fn mul_slow(a: u128, mut b: u128, m: u128) -> u128 {
+        let (mut a, mut res) = (a % m, 0);
+        while b > 0 {
+            if b & 1 == 1 {
+                res = (res + a) % m;
+            }
+            a = (a << 1) % m;
+            b >>= 1;
+        }
+        res
+    }
+
+    #[test]
+    fn test_mul_wide() {
+        assert_eq!(mul_wide(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+        assert_eq!(mul_wide(1 << 100, 1 << 100), (1 << 72, 0));
+        assert_eq!(mul_wide(12345, 6789), (0, 12345 * 6789));
+    }
+
+    #[test]
+    fn test_operations() {
+        for m in [
+            1,
+            2,
+            7,
+            1 << 20,
+            10u128.pow(15) << 40,
+            MAX_MODULUS,
+            MAX_MODULUS - 1,
+        ] {
+            let modulus = Modulus::new(m);
+            let (a, b) = (u128::MAX - 5, m / 3 + 1);
+
+            assert_eq!(modulus.mul(a, b), mul_slow(a, b, m), "{m}");
+            assert_eq!(modulus.add(m - 1, m - 1), m.saturating_sub(2), "{m}");
+            assert_eq!(modulus.sub(0, 1), m - 1, "{m}");
+            assert_eq!(modulus.pow(3, 0), 1 % m, "{m}");
+        }
+
+        let modulus = Modulus::new(10u128.pow(15));
+        assert_eq!(modulus.pow(12, 3456), modulus.pow(144, 1728));
+        assert_eq!(modulus.pow(2, 10), 1024);
+    }
+
+    #[test]
+    fn test_matches_slow_arithmetic() {
+        property::check(
+            |s| {
+                let m = s.int(1u64..=u64::MAX) as u128 * s.int(1u64..=1 << 63) as u128;
+                let a = s.int(0u64..=u64::MAX) as u128 * s.int(0u64..=u64::MAX) as u128;
+                let b = s.int(0u64..=u64::MAX) as u128;
+                (m.min(MAX_MODULUS), a, b, s.int(0u64..=300))
+            },
+            |&(m, a, b, exp)| {
+                let modulus = Modulus::new(m);
+                let pow = (0..exp).fold(1 % m, |p, _| mul_slow(p, a, m));
+                modulus.mul(a, b) == mul_slow(a, b, m) && modulus.pow(a, exp) == pow
+            },
+        );
+    }
+}
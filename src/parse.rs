@@ -0,0 +1,296 @@
+// Line-oriented parsing of the puzzle inputs.
+//
+// A `Line` is read from left to right; every failure is reported with the line and the
+// column where it happened. Blank lines separate the blocks of the multi-part inputs.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Line of the input, consumed from left to right.
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    /// from 1
+    number: usize,
+    text: &'a str,
+    /// what remains to be read
+    rest: &'a str,
+}
+
+impl<'a> Line<'a> {
+    fn new(number: usize, text: &'a str) -> Self {
+        let text = text.trim_end();
+        Self {
+            number,
+            text,
+            rest: text,
+        }
+    }
+
+    /// Column of the next character, from 1.
+    fn column(&self) -> usize {
+        let read = self.text.len() - self.rest.len();
+        self.text[..read].chars().count() + 1
+    }
+
+    /// Error located at the next character.
+    pub fn error(&self, message: impl fmt::Display) -> String {
+        format!("line {}, column {}: {message}", self.number, self.column())
+    }
+
+    /// What comes next, for error messages.
+    fn found(&self) -> String {
+        match self.rest.split_whitespace().next() {
+            Some(token) => format!("{token:?}"),
+            None => "end of line".to_string(),
+        }
+    }
+
+    fn skip_blanks(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    /// True if only blanks remain.
+    pub fn at_end(&self) -> bool {
+        self.rest.trim_start().is_empty()
+    }
+
+    /// Fails unless only blanks remain.
+    pub fn end(mut self) -> Result<(), String> {
+        self.skip_blanks();
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(format!("unexpected {}", self.found())))
+        }
+    }
+
+    /// Consumes `literal` if the line continues with it.
+    pub fn eat(&mut self, literal: &str) -> bool {
+        match self.rest.strip_prefix(literal) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Consumes `literal`, which must come next.
+    pub fn literal(&mut self, literal: &str) -> Result<(), String> {
+        if self.eat(literal) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {literal:?}, found {}", self.found())))
+        }
+    }
+
+    /// Next character, not consumed.
+    pub fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    pub fn char(&mut self) -> Result<char, String> {
+        let ch = self
+            .peek()
+            .ok_or_else(|| self.error("unexpected end of line"))?;
+        self.rest = &self.rest[ch.len_utf8()..];
+        Ok(ch)
+    }
+
+    /// Characters up to the next blank, after the blanks.
+    pub fn word(&mut self) -> Result<&'a str, String> {
+        self.skip_blanks();
+        let len = self
+            .rest
+            .find(char::is_whitespace)
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            return Err(self.error("expected a word, found end of line"));
+        }
+        let (word, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(word)
+    }
+
+    /// Decimal integer with an optional sign, after the blanks.
+    pub fn int<T>(&mut self) -> Result<T, String>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.skip_blanks();
+        let sign = usize::from(self.rest.starts_with(['+', '-']));
+        let len = sign
+            + self.rest[sign..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(self.rest.len() - sign);
+        if len == sign {
+            return Err(self.error(format!("expected a number, found {}", self.found())));
+        }
+
+        let (digits, rest) = self.rest.split_at(len);
+        let n = digits
+            .parse()
+            .map_err(|e| self.error(format!("invalid number {digits}: {e}")))?;
+        self.rest = rest;
+        Ok(n)
+    }
+
+    /// Characters up to `delimiter`, which is consumed too.
+    pub fn until(&mut self, delimiter: &str) -> Result<&'a str, String> {
+        let Some((before, after)) = self.rest.split_once(delimiter) else {
+            return Err(self.error(format!("expected {delimiter:?}")));
+        };
+        self.rest = after;
+        Ok(before)
+    }
+
+    /// Everything that remains.
+    pub fn rest(&mut self) -> &'a str {
+        std::mem::take(&mut self.rest)
+    }
+}
+
+/// Non-blank lines of `input`, without their trailing blanks.
+pub fn lines(input: &str) -> impl Iterator<Item = Line<'_>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, text)| Line::new(i + 1, text))
+        .filter(|line| !line.at_end())
+}
+
+/// Lines separated from the others by blank lines.
+#[derive(Clone, Debug)]
+pub struct Block<'a> {
+    /// never empty
+    lines: Vec<Line<'a>>,
+}
+
+impl<'a> Block<'a> {
+    pub fn lines(&self) -> impl Iterator<Item = Line<'a>> + '_ {
+        self.lines.iter().copied()
+    }
+
+    /// First line, which names or describes the block in most formats.
+    pub fn header(&self) -> Line<'a> {
+        self.lines[0]
+    }
+
+    /// Lines after the header.
+    pub fn body(&self) -> impl Iterator<Item = Line<'a>> + '_ {
+        self.lines().skip(1)
+    }
+
+    /// Line `<label>: <value>`, positioned on the value.
+    pub fn field(&self, label: &str) -> Result<Line<'a>, String> {
+        self.lines()
+            .find_map(|mut line| {
+                (line.eat(label) && line.eat(":")).then(|| {
+                    line.skip_blanks();
+                    line
+                })
+            })
+            .ok_or_else(|| {
+                self.header()
+                    .error(format!("missing {label:?} in this block"))
+            })
+    }
+}
+
+/// Blocks of consecutive non-blank lines.
+pub fn blocks(input: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut current = Vec::new();
+
+    for (i, text) in input.lines().enumerate() {
+        let line = Line::new(i + 1, text);
+        if line.at_end() {
+            if !current.is_empty() {
+                blocks.push(Block {
+                    lines: std::mem::take(&mut current),
+                });
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(Block { lines: current });
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> Line<'_> {
+        Line::new(1, text)
+    }
+
+    #[test]
+    fn test_tokens() {
+        let mut l = line("(12, -3) r=7 abc  ");
+        assert_eq!(l.literal("("), Ok(()));
+        assert_eq!(l.int(), Ok(12u32));
+        assert_eq!(l.literal(","), Ok(()));
+        assert_eq!(l.int(), Ok(-3i64));
+        assert_eq!(l.until("r="), Ok(") "));
+        assert_eq!(l.int(), Ok(7u8));
+        assert!(!l.at_end());
+        assert_eq!(l.word(), Ok("abc"));
+        assert!(l.at_end());
+        assert_eq!(l.end(), Ok(()));
+
+        let mut l = line("ab");
+        assert!(!l.eat("b"));
+        assert_eq!((l.peek(), l.char(), l.rest()), (Some('a'), Ok('a'), "b"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut l = line("plant x");
+        assert_eq!(l.word(), Ok("plant"));
+        assert_eq!(
+            l.int::<u64>(),
+            Err("line 1, column 7: expected a number, found \"x\"".to_string())
+        );
+        assert_eq!(l.column(), 7);
+
+        assert_eq!(
+            line("300").int::<u8>(),
+            Err(
+                "line 1, column 1: invalid number 300: number too large to fit in target type"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            line("a -> b").literal("b"),
+            Err("line 1, column 1: expected \"b\", found \"a\"".to_string())
+        );
+        assert!(line("1 2").end().is_err());
+        assert!(line("").word().is_err());
+        assert!(line("").char().is_err());
+        assert!(line("a").until(":").is_err());
+    }
+
+    #[test]
+    fn test_lines_and_blocks() {
+        let input = "a: 1\r\n\n\n  \nb: 2\nc: 3  \n\n";
+        let numbers: Vec<usize> = lines(input).map(|l| l.number).collect();
+        assert_eq!(numbers, [1, 5, 6]);
+
+        let blocks = blocks(input);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].header().number, 5);
+        assert_eq!(blocks[1].body().count(), 1);
+        assert_eq!(blocks[1].lines().count(), 2);
+        assert_eq!(blocks[1].field("c").and_then(|mut l| l.int()), Ok(3));
+        assert_eq!(
+            blocks[0].field("b").map(|_| ()),
+            Err("line 1, column 1: missing \"b\" in this block".to_string())
+        );
+    }
+}
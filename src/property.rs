@@ -0,0 +1,298 @@
+// Minimal property-based testing.
+//
+// Generators are plain functions drawing values from a `Source`. The source records
+// every choice it makes, so a failing case is shrunk by simplifying the recorded
+// choices (removing some, making others smaller) and generating the value again:
+// any generator, including day-specific ones, shrinks without extra code.
+//
+// The seed and the number of cases can be set with the PROPERTY_SEED and
+// PROPERTY_CASES environment variables.
+
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use crate::grid::Grid;
+use crate::rng::Rng;
+
+const DEFAULT_SEED: u64 = 0x5eed;
+const DEFAULT_CASES: u64 = 100;
+const MAX_SHRINK_ATTEMPTS: usize = 2000;
+
+/// Integer types that can be drawn from a `Source`.
+pub trait Int: Copy {
+    fn to_i128(self) -> i128;
+    fn from_i128(value: i128) -> Self;
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {
+        $(impl Int for $t {
+            fn to_i128(self) -> i128 {
+                self as i128
+            }
+            fn from_i128(value: i128) -> Self {
+                value as $t
+            }
+        })*
+    };
+}
+
+impl_int!(u8, u32, u64, usize, i32, i64);
+
+/// Provider of the random choices behind generated values.
+pub struct Source {
+    rng: Option<Rng>,
+    prefix: Vec<u64>,
+    choices: Vec<u64>,
+}
+
+impl Source {
+    fn random(seed: u64) -> Self {
+        Self {
+            rng: Some(Rng::new(seed)),
+            prefix: Vec::new(),
+            choices: Vec::new(),
+        }
+    }
+
+    /// Replays `choices`, then draws zeros (the simplest choice) once they are exhausted.
+    fn replay(choices: Vec<u64>) -> Self {
+        Self {
+            rng: None,
+            prefix: choices,
+            choices: Vec::new(),
+        }
+    }
+
+    /// Value in `0..n`.
+    fn draw(&mut self, n: u128) -> u128 {
+        assert!(n > 0 && n <= 1 << 64, "unsupported range size");
+
+        let raw = match (self.prefix.get(self.choices.len()), &mut self.rng) {
+            (Some(&raw), _) => raw,
+            (None, Some(rng)) => rng.next(),
+            (None, None) => 0,
+        };
+        let value = u128::from(raw) % n;
+        self.choices.push(value as u64);
+        value
+    }
+
+    /// Integer in `range`, shrinking towards its start.
+    pub fn int<T: Int>(&mut self, range: RangeInclusive<T>) -> T {
+        let (lo, hi) = (range.start().to_i128(), range.end().to_i128());
+        assert!(lo <= hi, "empty range");
+        T::from_i128(lo + self.draw((hi - lo + 1) as u128) as i128)
+    }
+
+    /// Shrinks towards `false`.
+    pub fn bool(&mut self) -> bool {
+        self.draw(2) == 1
+    }
+
+    /// One of `items`, shrinking towards the first one.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.int(0..=items.len() - 1)]
+    }
+
+    /// Vector of `len` elements, shrinking towards shorter vectors.
+    pub fn vec<T>(
+        &mut self,
+        len: RangeInclusive<usize>,
+        mut element: impl FnMut(&mut Self) -> T,
+    ) -> Vec<T> {
+        let len = self.int(len);
+        (0..len).map(|_| element(self)).collect()
+    }
+
+    /// Rectangular grid, shrinking towards fewer rows and columns.
+    pub fn grid<T>(
+        &mut self,
+        rows: RangeInclusive<usize>,
+        cols: RangeInclusive<usize>,
+        mut cell: impl FnMut(&mut Self) -> T,
+    ) -> Grid<T> {
+        let rows = self.int(rows);
+        let cols = self.int(cols);
+        Grid::from_fn(rows, cols, |_| cell(self))
+    }
+}
+
+thread_local! {
+    static SHRINKING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Silences panic messages of the current thread while it is shrinking:
+/// the failing case has already been reported.
+fn install_panic_hook() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let default = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !SHRINKING.with(Cell::get) {
+                default(info);
+            }
+        }));
+    });
+}
+
+fn env_or(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Generates a value from `choices`. Returns it with the choices actually used,
+/// and whether the property holds (a panic counts as a failure).
+fn run<T>(
+    mut source: Source,
+    generate: &impl Fn(&mut Source) -> T,
+    property: &impl Fn(&T) -> bool,
+) -> (T, Vec<u64>, bool) {
+    let value = generate(&mut source);
+    let holds = panic::catch_unwind(AssertUnwindSafe(|| property(&value))).unwrap_or(false);
+    (value, source.choices, holds)
+}
+
+/// Candidate simplifications of a choice sequence, simplest first.
+fn simplifications(choices: &[u64]) -> Vec<Vec<u64>> {
+    let mut candidates = Vec::new();
+
+    for size in [8, 4, 2, 1] {
+        for start in (0..choices.len()).rev() {
+            if start + size <= choices.len() {
+                let mut c = choices.to_vec();
+                c.drain(start..start + size);
+                candidates.push(c);
+            }
+        }
+    }
+
+    for (i, &choice) in choices.iter().enumerate() {
+        for smaller in [0, choice / 2, choice.saturating_sub(1)] {
+            if smaller < choice {
+                let mut c = choices.to_vec();
+                c[i] = smaller;
+                candidates.push(c);
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Checks that `property` holds for values produced by `generate`.
+/// On failure, panics with a shrunk counterexample.
+pub fn check<T: Debug>(generate: impl Fn(&mut Source) -> T, property: impl Fn(&T) -> bool) {
+    let seed = env_or("PROPERTY_SEED", DEFAULT_SEED);
+    let cases = env_or("PROPERTY_CASES", DEFAULT_CASES);
+
+    for case in 0..cases {
+        let (value, choices, holds) = run(
+            Source::random(seed.wrapping_add(case)),
+            &generate,
+            &property,
+        );
+        if holds {
+            continue;
+        }
+
+        let (value, shrinks) = shrink(value, choices, &generate, &property);
+        panic!(
+            "property failed (seed {seed}, case {case}, {shrinks} shrinks)\nminimal counterexample: {value:#?}"
+        );
+    }
+}
+
+fn shrink<T>(
+    mut value: T,
+    mut choices: Vec<u64>,
+    generate: &impl Fn(&mut Source) -> T,
+    property: &impl Fn(&T) -> bool,
+) -> (T, usize) {
+    install_panic_hook();
+    SHRINKING.with(|s| s.set(true));
+
+    let mut shrinks = 0;
+    let mut attempts = 0;
+
+    'outer: while attempts < MAX_SHRINK_ATTEMPTS {
+        for candidate in simplifications(&choices) {
+            attempts += 1;
+            let (new_value, used, holds) = run(Source::replay(candidate), generate, property);
+            if !holds && (used.len(), &used) < (choices.len(), &choices) {
+                value = new_value;
+                choices = used;
+                shrinks += 1;
+                continue 'outer;
+            }
+            if attempts >= MAX_SHRINK_ATTEMPTS {
+                break;
+            }
+        }
+        break;
+    }
+
+    SHRINKING.with(|s| s.set(false));
+    (value, shrinks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the panic message of `f`.
+    fn failure(f: impl FnOnce()) -> String {
+        let err = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+        err.downcast_ref::<String>().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_passing_property() {
+        check(|s| s.int(0u32..=10), |&x| x <= 10);
+        check(
+            |s| s.grid(1..=4, 2..=5, |s| s.bool()),
+            |g| (1..=4).contains(&g.rows()) && (2..=5).contains(&g.cols()),
+        );
+    }
+
+    #[test]
+    fn test_shrink_int() {
+        let message = failure(|| check(|s| s.int(0u64..=1_000_000), |&x| x < 1000));
+        assert!(message.ends_with("counterexample: 1000"), "{message}");
+    }
+
+    #[test]
+    fn test_shrink_negative_int() {
+        let message = failure(|| check(|s| s.int(-50i64..=50), |&x| x > -20));
+        assert!(message.ends_with("counterexample: -50"), "{message}");
+    }
+
+    #[test]
+    fn test_shrink_vec() {
+        let message = failure(|| {
+            check(
+                |s| s.vec(0..=20, |s| s.int(0u32..=100)),
+                |v| v.iter().sum::<u32>() < 50,
+            )
+        });
+        let minimal = message.split("counterexample: ").nth(1).unwrap();
+        assert_eq!(minimal.split_whitespace().collect::<String>(), "[50,]");
+    }
+
+    #[test]
+    fn test_panic_is_failure() {
+        let message = failure(|| {
+            check(
+                |s| s.vec(0..=5, |s| s.int(0usize..=9)),
+                |v| v.iter().all(|&x| [1, 2, 3][x % 4] > 0),
+            )
+        });
+        let minimal = message.split("counterexample: ").nth(1).unwrap();
+        assert_eq!(minimal.split_whitespace().collect::<String>(), "[3,]");
+    }
+}
@@ -0,0 +1,175 @@
+// Pictures of the solutions, shown with `--render`: grids drawn in the terminal with
+// ANSI colours, SVG documents for the scenes in the plane, and Graphviz documents for
+// the graphs.
+
+use std::fmt::{self, Write};
+
+use crate::grid::{Grid, Pos};
+
+/// Colour of a highlighted cell or of a shape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+}
+
+impl Color {
+    /// ANSI SGR code of the foreground colour.
+    fn ansi(self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+        }
+    }
+
+    fn svg(self) -> &'static str {
+        match self {
+            Color::Red => "crimson",
+            Color::Green => "seagreen",
+            Color::Yellow => "goldenrod",
+            Color::Blue => "steelblue",
+        }
+    }
+}
+
+/// What a day renders.
+pub enum Picture {
+    /// Text with ANSI escape codes, printed as is.
+    Terminal(String),
+    /// Document written to a `.svg` file.
+    Svg(Svg),
+    /// Graphviz document written to a `.dot` file.
+    Dot(String),
+}
+
+/// Draws `grid` one character per cell, the cells for which `highlight` gives a colour
+/// being bold and coloured.
+pub fn ansi_grid<T: fmt::Display>(
+    grid: &Grid<T>,
+    highlight: impl Fn(Pos) -> Option<Color>,
+) -> String {
+    let mut out = String::new();
+    for (pos, cell) in grid.iter() {
+        match highlight(pos) {
+            Some(color) => write!(out, "\x1b[1;{}m{cell}\x1b[0m", color.ansi()),
+            None => write!(out, "{cell}"),
+        }
+        .unwrap();
+        if pos.1 + 1 == grid.cols() {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// SVG document, in the coordinates of the scene: y goes down as in the puzzles.
+pub struct Svg {
+    /// min x, min y, width, height
+    view_box: (f64, f64, f64, f64),
+    elements: String,
+}
+
+impl Svg {
+    /// Document showing the rectangle from `(x0, y0)` to `(x1, y1)`, with a margin.
+    pub fn new((x0, y0): (f64, f64), (x1, y1): (f64, f64)) -> Self {
+        let margin = 0.05 * (x1 - x0).max(y1 - y0).max(1.0);
+        Self {
+            view_box: (
+                x0 - margin,
+                y0 - margin,
+                x1 - x0 + 2.0 * margin,
+                y1 - y0 + 2.0 * margin,
+            ),
+            elements: String::new(),
+        }
+    }
+
+    /// Width of the lines, in the coordinates of the scene.
+    fn stroke(&self) -> f64 {
+        self.view_box.2.max(self.view_box.3) / 500.0
+    }
+
+    /// Outline of a circle, lightly filled.
+    pub fn circle(&mut self, (cx, cy): (f64, f64), r: f64, color: Color) {
+        writeln!(
+            self.elements,
+            r#"<circle cx="{cx}" cy="{cy}" r="{r}" fill="{0}" fill-opacity="0.1" stroke="{0}" stroke-width="{1}"/>"#,
+            color.svg(),
+            self.stroke()
+        )
+        .unwrap();
+    }
+
+    /// Point, drawn as a small disc.
+    pub fn dot(&mut self, (cx, cy): (f64, f64), color: Color) {
+        writeln!(
+            self.elements,
+            r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="{}"/>"#,
+            4.0 * self.stroke(),
+            color.svg()
+        )
+        .unwrap();
+    }
+
+    /// Text whose bottom left corner is at `(x, y)`.
+    pub fn label(&mut self, (x, y): (f64, f64), text: &str) {
+        writeln!(
+            self.elements,
+            r#"<text x="{x}" y="{y}" font-size="{}" font-family="sans-serif">{}</text>"#,
+            12.0 * self.stroke(),
+            escape(text)
+        )
+        .unwrap();
+    }
+}
+
+impl fmt::Display for Svg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (x, y, width, height) = self.view_box;
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{x} {y} {width} {height}" width="800" height="{}">"#,
+            (800.0 * height / width).round()
+        )?;
+        write!(f, "{}", self.elements)?;
+        writeln!(f, "</svg>")
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ansi_grid() {
+        let grid = Grid::from_fn(2, 3, |(r, c)| r * 3 + c);
+        let drawing = ansi_grid(&grid, |pos| (pos == (1, 1)).then_some(Color::Red));
+        assert_eq!(drawing, "012\n3\x1b[1;31m4\x1b[0m5\n");
+        assert_eq!(ansi_grid(&grid, |_| None), format!("{grid}\n"));
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut svg = Svg::new((0.0, 0.0), (100.0, 50.0));
+        svg.circle((10.0, 20.0), 5.0, Color::Blue);
+        svg.dot((30.0, 40.0), Color::Green);
+        svg.label((1.0, 2.0), "a < b");
+
+        let document = svg.to_string();
+        assert!(document.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-5 -5 110 60" width="800" height="436">"#));
+        assert!(document.contains(r#"<circle cx="10" cy="20" r="5" fill="steelblue""#));
+        assert!(document.contains(r#"fill="seagreen"/>"#));
+        assert!(document.contains(">a &lt; b</text>"));
+        assert!(document.ends_with("</svg>\n"));
+    }
+}
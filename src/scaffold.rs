@@ -0,0 +1,291 @@
+// Scaffolding of a new day: module skeleton, registration in main.rs and empty input.
+
+use std::fs;
+use std::path::Path;
+
+const MAIN_RS: &str = "src/main.rs";
+
+pub fn new_day(day: u32, title: &str) -> Result<(), String> {
+    if !(1..=25).contains(&day) {
+        return Err(format!("day {day} is not part of the event (1-25)"));
+    }
+
+    let module_path = format!("src/day{day:02}.rs");
+    let input_path = format!("inputs/day{day:02}.txt");
+
+    for path in [&module_path, &input_path] {
+        if Path::new(path).exists() {
+            return Err(format!("{path} already exists"));
+        }
+    }
+
+    let main_rs = fs::read_to_string(MAIN_RS).map_err(|e| format!("cannot read {MAIN_RS}: {e}"))?;
+    let main_rs = register(&main_rs, day)?;
+
+    fs::write(&module_path, module_skeleton(day, title))
+        .map_err(|e| format!("cannot write {module_path}: {e}"))?;
+    fs::write(&input_path, "").map_err(|e| format!("cannot write {input_path}: {e}"))?;
+    fs::write(MAIN_RS, main_rs).map_err(|e| format!("cannot write {MAIN_RS}: {e}"))?;
+
+    println!("created {module_path}");
+    println!("created {input_path}");
+    println!("registered day {day} in {MAIN_RS}");
+
+    Ok(())
+}
+
+fn module_skeleton(day: u32, title: &str) -> String {
+    format!(
+        r#"// Day {day}: {title}
+// https://lovemathboy.github.io/day{day}.html
+
+pub fn solve(input: &str) -> Result<(String, String), String> {{
+    Ok((part1(input).to_string(), part2(input).to_string()))
+}}
+
+fn part1(_input: &str) -> u64 {{
+    0
+}}
+
+fn part2(_input: &str) -> u64 {{
+    0
+}}
+
+/// Example of the puzzle statement.
+pub const EXAMPLE: &str = "\
+";
+
+#[cfg(test)]
+mod tests {{
+    use super::*;
+
+    #[test]
+    fn test_part1() {{
+        assert_eq!(part1(EXAMPLE), 0);
+    }}
+
+    #[test]
+    fn test_part2() {{
+        assert_eq!(part2(EXAMPLE), 0);
+    }}
+}}
+"#
+    )
+}
+
+/// Adds the `mod` declaration, the dispatch arm and an empty `SOLUTIONS` row for `day`.
+fn register(main_rs: &str, day: u32) -> Result<String, String> {
+    let mut lines: Vec<String> = main_rs.lines().map(String::from).collect();
+
+    insert_entry(
+        &mut lines,
+        |line| {
+            line.strip_prefix("mod day")?
+                .strip_suffix(';')?
+                .parse()
+                .ok()
+        },
+        format!("mod day{day:02};"),
+        day,
+        "module declarations",
+    )?;
+
+    insert_entry(
+        &mut lines,
+        |line| {
+            let (key, _) = line.trim().split_once(" => ")?;
+            key.parse().ok()
+        },
+        format!("        {day} => Some(Puzzle::new(day{day:02}::solve, day{day:02}::EXAMPLE)),"),
+        day,
+        "day dispatch",
+    )?;
+
+    insert_entry(
+        &mut lines,
+        |line| {
+            let (key, _) = line.trim().strip_prefix('(')?.split_once(", \"")?;
+            key.parse().ok()
+        },
+        format!("    ({day}, \"\", \"\"),"),
+        day,
+        "SOLUTIONS",
+    )?;
+
+    let mut main_rs = lines.join("\n");
+    main_rs.push('\n');
+    Ok(main_rs)
+}
+
+/// Inserts `new_line` among the lines for which `key` returns a day number,
+/// before the first entry with a greater day (entries are not required to be sorted).
+/// An entry may be wrapped over the following lines, indented deeper and closed at its
+/// own indentation.
+fn insert_entry(
+    lines: &mut Vec<String>,
+    key: impl Fn(&str) -> Option<u32>,
+    new_line: String,
+    day: u32,
+    what: &str,
+) -> Result<(), String> {
+    let entries: Vec<(usize, u32)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| key(line).map(|d| (i, d)))
+        .collect();
+
+    if entries.iter().any(|&(_, d)| d == day) {
+        return Err(format!("day {day} is already registered in {what}"));
+    }
+
+    let Some(&(last, _)) = entries.last() else {
+        return Err(format!("cannot locate {what} in {MAIN_RS}"));
+    };
+
+    let pos = match entries.iter().find(|&&(_, d)| d > day) {
+        Some(&(i, _)) => i,
+        None => {
+            let indent = |line: &str| line.len() - line.trim_start().len();
+            let depth = indent(&lines[last]);
+            let mut end = last + 1;
+            while end < lines.len() && indent(&lines[end]) > depth {
+                end += 1;
+            }
+            if end < lines.len()
+                && end > last + 1
+                && lines[end].trim_start().starts_with([')', ']', '}'])
+            {
+                end += 1;
+            }
+            end
+        }
+    };
+
+    lines.insert(pos, new_line);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN_RS: &str = "\
+mod day13;
+mod day15;
+
+const SOLUTIONS: &[(u32, &str, &str)] = &[
+    (13, \"1\", \"2\"),
+    (15, \"3\", \"4\"),
+];
+
+fn puzzle(day: u32) -> Option<Puzzle> {
+    match day {
+        15 => Some(Puzzle::new(day15::solve, day15::EXAMPLE)),
+        13 => Some(Puzzle::new(day13::solve, day13::EXAMPLE)),
+        _ => None,
+    }
+}
+";
+
+    #[test]
+    fn test_register() {
+        assert_eq!(
+            register(MAIN_RS, 14).unwrap(),
+            "\
+mod day13;
+mod day14;
+mod day15;
+
+const SOLUTIONS: &[(u32, &str, &str)] = &[
+    (13, \"1\", \"2\"),
+    (14, \"\", \"\"),
+    (15, \"3\", \"4\"),
+];
+
+fn puzzle(day: u32) -> Option<Puzzle> {
+    match day {
+        14 => Some(Puzzle::new(day14::solve, day14::EXAMPLE)),
+        15 => Some(Puzzle::new(day15::solve, day15::EXAMPLE)),
+        13 => Some(Puzzle::new(day13::solve, day13::EXAMPLE)),
+        _ => None,
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_register_last() {
+        let main_rs = register(MAIN_RS, 20).unwrap();
+        assert!(main_rs.contains("mod day15;\nmod day20;\n"));
+        assert!(main_rs.contains("(15, \"3\", \"4\"),\n    (20, \"\", \"\"),\n];"));
+        assert!(main_rs.contains(
+            "day13::EXAMPLE)),\n        20 => Some(Puzzle::new(day20::solve, day20::EXAMPLE)),"
+        ));
+    }
+
+    #[test]
+    fn test_register_wrapped() {
+        let main_rs = MAIN_RS.replace(
+            "        13 => Some(Puzzle::new(day13::solve, day13::EXAMPLE)),\n",
+            "        13 => Some(
+            Puzzle::new(day13::solve, day13::EXAMPLE)
+                .with_render(day13::render)
+                .with_inspect(day13::inspect),
+        ),
+",
+        );
+        assert!(register(&main_rs, 13).is_err());
+        let main_rs = register(&main_rs, 20).unwrap();
+        assert!(main_rs.contains(
+            "                .with_inspect(day13::inspect),
+        ),
+        20 => Some(Puzzle::new(day20::solve, day20::EXAMPLE)),
+        _ => None,"
+        ));
+    }
+
+    #[test]
+    fn test_register_block() {
+        let main_rs = MAIN_RS
+            .replace(
+                "        13 => Some(Puzzle::new(day13::solve, day13::EXAMPLE)),\n",
+                "",
+            )
+            .replace(
+                "        15 => Some(",
+                "        13 => {
+            Some(Puzzle::new(day13::solve, day13::EXAMPLE).with_queries(day13::queries))
+        }
+        15 => Some(",
+            );
+        assert!(register(&main_rs, 13).is_err());
+        assert!(register(&main_rs, 12).unwrap().contains(
+            "        12 => Some(Puzzle::new(day12::solve, day12::EXAMPLE)),
+        13 => {"
+        ));
+        let main_rs = main_rs.replace(
+            "        15 => Some(Puzzle::new(day15::solve, day15::EXAMPLE)),\n",
+            "",
+        );
+        let main_rs = register(&main_rs, 20).unwrap();
+        assert!(main_rs.contains(
+            "day13::EXAMPLE).with_queries(day13::queries))
+        }
+        20 => Some(Puzzle::new(day20::solve, day20::EXAMPLE)),
+        _ => None,"
+        ));
+    }
+
+    #[test]
+    fn test_register_existing() {
+        assert!(register(MAIN_RS, 15).is_err());
+    }
+
+    #[test]
+    fn test_skeleton_padding() {
+        let module = module_skeleton(3, "Test");
+        assert!(module.starts_with("// Day 3: Test\n"));
+        assert!(module.contains("pub fn solve(input: &str)"));
+    }
+}
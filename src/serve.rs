@@ -0,0 +1,390 @@
+// Local HTTP dashboard: a browser on the same machine lists the days and runs them.
+//
+// GET endpoints, each connection on its own thread:
+//   /                 HTML page of the days
+//   /run/NN           HTML page of the results of day NN, solved on demand
+//   /api/days         JSON catalog of the days
+//   /api/run/NN       JSON results of day NN, solved on demand
+
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{DAYS, expected, input_path, no_solver, puzzle, trace};
+
+/// Result of a run of a day on its input.
+struct Run {
+    day: u32,
+    /// answers, or the reason why there are none
+    answers: Result<(String, String), String>,
+    /// answers to the queries of the input
+    queries: Vec<String>,
+    expected: (&'static str, &'static str),
+    millis: u128,
+}
+
+impl Run {
+    fn new(day: u32) -> Self {
+        let start = Instant::now();
+        let solved = match (puzzle(day), std::fs::read_to_string(input_path(day))) {
+            (None, _) => Err(no_solver(day)),
+            (_, Err(_)) => Err(format!("input not found for day {day}")),
+            (Some(puzzle), Ok(input)) => {
+                let _scope = trace::scope(day);
+                puzzle.solve_with_queries(&input)
+            }
+        };
+        let (answers, queries) = match solved {
+            Ok((answers, queries)) => (Ok(answers), queries),
+            Err(e) => (Err(e), Vec::new()),
+        };
+        Self {
+            day,
+            answers,
+            queries,
+            expected: expected(day),
+            millis: start.elapsed().as_millis(),
+        }
+    }
+
+    /// Whether each part matches its known answer, `None` if unknown.
+    fn checks(&self) -> [Option<bool>; 2] {
+        let (p1, p2) = self.answers.as_ref().map_or(("", ""), |(p1, p2)| (p1, p2));
+        let check = |answer: &str, expected: &str| {
+            (!expected.is_empty() && self.answers.is_ok()).then(|| answer == expected)
+        };
+        [check(p1, self.expected.0), check(p2, self.expected.1)]
+    }
+
+    fn json(&self) -> String {
+        let mut json = format!("{{\"day\":{}", self.day);
+        match &self.answers {
+            Ok((p1, p2)) => {
+                let [ok1, ok2] = self.checks();
+                let correct = |ok: Option<bool>| ok.map_or("null".to_string(), |b| b.to_string());
+                let queries: Vec<String> = self.queries.iter().map(|q| json_string(q)).collect();
+                write!(
+                    json,
+                    ",\"answers\":[{},{}],\"queries\":[{}],\"correct\":[{},{}]",
+                    json_string(p1),
+                    json_string(p2),
+                    queries.join(","),
+                    correct(ok1),
+                    correct(ok2)
+                )
+                .unwrap();
+            }
+            Err(e) => write!(json, ",\"error\":{}", json_string(e)).unwrap(),
+        }
+        write!(
+            json,
+            ",\"expected\":[{},{}],\"millis\":{}}}",
+            json_string(self.expected.0),
+            json_string(self.expected.1),
+            self.millis
+        )
+        .unwrap();
+        json
+    }
+
+    fn html(&self) -> String {
+        let mut body = format!("<h1>Day {}</h1>\n", self.day);
+        match &self.answers {
+            Ok((p1, p2)) => {
+                for answer in &self.queries {
+                    writeln!(body, "<p><code>{}</code></p>", escape_html(answer)).unwrap();
+                }
+                body += "<table>\n";
+                for ((part, answer), ok) in (1..).zip([p1, p2]).zip(self.checks()) {
+                    let mark = match ok {
+                        Some(true) => " ✅",
+                        Some(false) => " ❌",
+                        None => "",
+                    };
+                    writeln!(
+                        body,
+                        "<tr><td>Part {part}</td><td><code>{}</code>{mark}</td></tr>",
+                        escape_html(answer)
+                    )
+                    .unwrap();
+                }
+                body += "</table>\n";
+            }
+            Err(e) => writeln!(body, "<p>error: {}</p>", escape_html(e)).unwrap(),
+        }
+        writeln!(
+            body,
+            "<p>{} ms</p>\n<p><a href=\"/\">All days</a></p>",
+            self.millis
+        )
+        .unwrap();
+        page(&format!("Day {}", self.day), &body)
+    }
+}
+
+/// Days with a solver, with whether their input is present.
+fn catalog() -> Vec<(u32, bool)> {
+    DAYS.filter(|&day| puzzle(day).is_some())
+        .map(|day| (day, std::path::Path::new(&input_path(day)).exists()))
+        .collect()
+}
+
+fn catalog_json() -> String {
+    let days: Vec<String> = catalog()
+        .into_iter()
+        .map(|(day, input)| {
+            let (p1, p2) = expected(day);
+            format!(
+                "{{\"day\":{day},\"input\":{input},\"expected\":[{},{}]}}",
+                json_string(p1),
+                json_string(p2)
+            )
+        })
+        .collect();
+    format!("[{}]", days.join(","))
+}
+
+fn catalog_html() -> String {
+    let mut body = "<h1>Advent of Lmbee</h1>\n<table>\n".to_string();
+    for (day, input) in catalog() {
+        let action = if input {
+            format!("<a href=\"/run/{day}\">run</a>")
+        } else {
+            "no input".to_string()
+        };
+        writeln!(body, "<tr><td>Day {day}</td><td>{action}</td></tr>").unwrap();
+    }
+    body += "</table>\n";
+    page("Advent of Lmbee", &body)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n{body}</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{status}\n"),
+        }
+    }
+}
+
+const HTML: &str = "text/html; charset=utf-8";
+const JSON: &str = "application/json";
+
+/// Response to the request line "METHOD /path HTTP/1.1".
+fn respond(request_line: &str) -> Response {
+    let mut words = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (words.next(), words.next()) else {
+        return Response::error("400 Bad Request");
+    };
+    if method != "GET" {
+        return Response::error("405 Method Not Allowed");
+    }
+
+    let path = target.split('?').next().unwrap_or_default();
+    let day = |rest: &str| rest.parse().ok().filter(|day| DAYS.contains(day));
+
+    match path {
+        "/" => Response::ok(HTML, catalog_html()),
+        "/api/days" => Response::ok(JSON, catalog_json()),
+        _ => {
+            if let Some(day) = path.strip_prefix("/api/run/").and_then(day) {
+                Response::ok(JSON, Run::new(day).json())
+            } else if let Some(day) = path.strip_prefix("/run/").and_then(day) {
+                Response::ok(HTML, Run::new(day).html())
+            } else {
+                Response::error("404 Not Found")
+            }
+        }
+    }
+}
+
+/// Time after which a client that stops sending or reading is dropped.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn handle(stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // headers are not used, but must be read before answering
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let response = respond(&request_line);
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Serves the dashboard on `127.0.0.1:port` until the process is stopped.
+/// Port 0 picks a free port; the address is printed on stdout.
+pub fn serve(port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("cannot listen on port {port}: {e}"))?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    println!("🌐 Serving on http://{address}/ (Ctrl-C to stop)");
+    std::io::stdout().flush().map_err(|e| e.to_string())?;
+
+    accept(listener);
+    Ok(())
+}
+
+/// Answers the connections to `listener`, each on its own thread so that a slow client
+/// does not hold up the others.
+fn accept(listener: TcpListener) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                thread::spawn(move || {
+                    if let Err(e) = handle(stream) {
+                        eprintln!("error: {e}");
+                    }
+                });
+            }
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_routes() {
+        let response = respond("GET / HTTP/1.1\r\n");
+        assert_eq!((response.status, response.content_type), ("200 OK", HTML));
+        assert!(response.body.contains("<tr><td>Day 14</td><td>"));
+
+        let response = respond("GET /api/days HTTP/1.1\r\n");
+        assert_eq!(response.content_type, JSON);
+        assert!(response.body.starts_with("[{\"day\":13,\"input\":"));
+        assert!(
+            response
+                .body
+                .contains("\"expected\":[\"75292\",\"824149222686720\"]")
+        );
+
+        assert_eq!(respond("GET /run/42 HTTP/1.1").status, "404 Not Found");
+        assert_eq!(respond("GET /nothing HTTP/1.1").status, "404 Not Found");
+        assert_eq!(respond("POST / HTTP/1.1").status, "405 Method Not Allowed");
+        assert_eq!(respond("").status, "400 Bad Request");
+    }
+
+    #[test]
+    fn test_run() {
+        let run = Run {
+            day: 17,
+            answers: Ok(("492".to_string(), "3".to_string())),
+            queries: vec!["total: 6 (part 1), 2 (part 2)".to_string()],
+            expected: ("492", "492"),
+            millis: 5,
+        };
+        assert_eq!(
+            run.json(),
+            "{\"day\":17,\"answers\":[\"492\",\"3\"],\
+             \"queries\":[\"total: 6 (part 1), 2 (part 2)\"],\"correct\":[true,false],\
+             \"expected\":[\"492\",\"492\"],\"millis\":5}"
+        );
+        assert!(run.html().contains("<td><code>492</code> ✅</td>"));
+        assert!(
+            run.html()
+                .contains("<p><code>total: 6 (part 1), 2 (part 2)</code></p>")
+        );
+
+        let run = Run {
+            answers: Err("line 1: \"x\" <unexpected>".to_string()),
+            expected: ("", ""),
+            ..run
+        };
+        assert_eq!(
+            run.json(),
+            "{\"day\":17,\"error\":\"line 1: \\\"x\\\" <unexpected>\",\
+             \"expected\":[\"\",\"\"],\"millis\":5}"
+        );
+        assert!(
+            run.html()
+                .contains("error: line 1: &quot;x&quot; &lt;unexpected&gt;")
+        );
+    }
+
+    #[test]
+    fn test_idle_connection() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || accept(listener));
+
+        // a client that connects and sends nothing
+        let _idle = TcpStream::connect(address).unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT / 2)).unwrap();
+        stream.write_all(b"GET /api/days HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("}]"));
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+    }
+}
@@ -0,0 +1,148 @@
+// Narration of the solvers: intermediate results that explain an answer, written to
+// stderr so that the answers stay alone on stdout.
+//
+// Warnings are shown by default, the rest with `--explain`. Messages are tagged with
+// the day being solved, set by `scope`. The settings belong to the thread, which keeps
+// the tests independent of each other.
+
+use std::cell::Cell;
+#[cfg(test)]
+use std::cell::RefCell;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// Importance of a message, from the most to the least important.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// The solver cannot handle the input as expected.
+    Warn,
+    /// Key intermediate results.
+    Info,
+    /// Details of the intermediate results.
+    Debug,
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            _ => Err(format!("unknown level '{s}' (warn, info or debug)")),
+        }
+    }
+}
+
+thread_local! {
+    /// Least important level shown, `None` for silence.
+    static LEVEL: Cell<Option<Level>> = const { Cell::new(Some(Level::Warn)) };
+    static DAY: Cell<Option<u32>> = const { Cell::new(None) };
+    /// Messages kept instead of being written, see `capture`.
+    #[cfg(test)]
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+pub fn set_level(level: Option<Level>) {
+    LEVEL.set(level);
+}
+
+pub fn enabled(level: Level) -> bool {
+    LEVEL.get().is_some_and(|shown| level <= shown)
+}
+
+/// Tags the messages with `day` until the returned guard is dropped.
+pub fn scope(day: u32) -> Scope {
+    Scope {
+        previous: DAY.replace(Some(day)),
+    }
+}
+
+pub struct Scope {
+    previous: Option<u32>,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        DAY.set(self.previous);
+    }
+}
+
+/// Writes a message, whose level is enabled: use the macros instead.
+pub fn emit(level: Level, message: fmt::Arguments) {
+    let mut line = String::new();
+    if let Some(day) = DAY.get() {
+        write!(line, "[day {day}] ").unwrap();
+    }
+    if level == Level::Warn {
+        line.push_str("warning: ");
+    }
+    line.write_fmt(message).unwrap();
+
+    #[cfg(test)]
+    if CAPTURED
+        .with_borrow_mut(|captured| captured.as_mut().map(|c| c.push(line.clone())))
+        .is_some()
+    {
+        return;
+    }
+
+    eprintln!("{line}");
+}
+
+/// Messages emitted by `f` up to `level`, instead of writing them.
+#[cfg(test)]
+pub fn capture(level: Level, f: impl FnOnce()) -> Vec<String> {
+    let previous = LEVEL.replace(Some(level));
+    CAPTURED.set(Some(Vec::new()));
+    f();
+    LEVEL.set(previous);
+    CAPTURED.take().unwrap_or_default()
+}
+
+/// Writes a message at the given level, its arguments being formatted only if shown.
+macro_rules! message {
+    ($level:ident, $($arg:tt)*) => {
+        if $crate::trace::enabled($crate::trace::Level::$level) {
+            $crate::trace::emit($crate::trace::Level::$level, format_args!($($arg)*));
+        }
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => { $crate::trace::message!(Warn, $($arg)*) };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::trace::message!(Info, $($arg)*) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::trace::message!(Debug, $($arg)*) };
+}
+
+pub(crate) use {debug, info, message, warning};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_and_scope() {
+        let messages = capture(Level::Info, || {
+            warning!("a {}", 1);
+            {
+                let _scope = scope(7);
+                info!("b");
+                debug!("c");
+            }
+            info!("d");
+        });
+        assert_eq!(messages, ["warning: a 1", "[day 7] b", "d"]);
+
+        assert_eq!(capture(Level::Debug, || debug!("c")), ["c"]);
+        assert_eq!("debug".parse(), Ok(Level::Debug));
+        assert!("verbose".parse::<Level>().is_err());
+    }
+}
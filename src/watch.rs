@@ -0,0 +1,62 @@
+// Watch mode: re-run a day whenever one of its input files changes.
+
+use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Modification time and size of a file, `None` if it does not exist.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &str) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Calls `run` once, then again each time one of `paths` is modified, created or removed.
+/// The terminal is cleared before each run. Never returns: stop it with Ctrl-C.
+pub fn watch(paths: &[String], mut run: impl FnMut()) -> ! {
+    let mut last: Option<Vec<Stamp>> = None;
+
+    loop {
+        let stamps: Vec<Stamp> = paths.iter().map(|path| stamp(path)).collect();
+
+        if last.as_ref() != Some(&stamps) {
+            // clear screen and move the cursor home
+            print!("\x1b[2J\x1b[H");
+            run();
+            println!();
+            println!("👀 Watching {} (Ctrl-C to stop)", paths.join(", "));
+            let _ = io::stdout().flush();
+
+            last = Some(stamps);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp() {
+        let path = std::env::temp_dir().join(format!("watch-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        assert_eq!(stamp(path), None);
+
+        fs::write(path, "plant 5\n").unwrap();
+        let first = stamp(path);
+        assert!(first.is_some());
+
+        fs::write(path, "plant 5\nspray all\n").unwrap();
+        assert_ne!(stamp(path), first);
+
+        fs::remove_file(path).unwrap();
+        assert_eq!(stamp(path), None);
+    }
+}
@@ -0,0 +1,452 @@
+// End-to-end tests of the binary: day dispatch, answers checking, output and exit codes.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const BIN: &str = env!("CARGO_BIN_EXE_advent-of-lmbee");
+
+/// Days with a solver and an input in `inputs/`.
+const DAYS: std::ops::RangeInclusive<u32> = 13..=25;
+
+struct Output {
+    code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    let output = Command::new(BIN)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("cannot run the binary");
+
+    Output {
+        code: output.status.code(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    }
+}
+
+/// Runs from the repository root, with the real inputs.
+fn run_repo(args: &[&str]) -> Output {
+    run(Path::new(env!("CARGO_MANIFEST_DIR")), args)
+}
+
+/// Temporary working directory with the given `inputs/` files, removed on drop.
+struct Workdir(PathBuf);
+
+impl Workdir {
+    fn new(name: &str, inputs: &[(u32, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("cli-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("inputs")).unwrap();
+        for (day, content) in inputs {
+            fs::write(dir.join(format!("inputs/day{day:02}.txt")), content).unwrap();
+        }
+        Self(dir)
+    }
+}
+
+impl Drop for Workdir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Checks the output of a day whose both answers are correct.
+fn assert_solved(day: u32, stdout: &str) {
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{stdout}");
+    assert_eq!(lines[0], format!("🎁 Day {day}: "));
+
+    for (part, line) in (1..).zip(&lines[1..]) {
+        let prefix = format!("  Part {part}: ");
+        assert!(line.starts_with(&prefix), "{stdout}");
+        assert!(line.ends_with(" ✅"), "{stdout}");
+    }
+}
+
+#[test]
+fn test_every_day() {
+    for day in DAYS {
+        let output = run_repo(&[&day.to_string()]);
+        assert_eq!(output.code, Some(0), "day {day}: {}", output.stderr);
+        assert_eq!(output.stderr, "");
+        assert_solved(day, &output.stdout);
+    }
+}
+
+#[test]
+fn test_all_days() {
+    let output = run_repo(&[]);
+    assert_eq!(output.code, Some(0), "{}", output.stderr);
+    assert_eq!(output.stderr, "");
+
+    // days are run in order, separated by an empty line
+    let blocks: Vec<&str> = output.stdout.split_terminator("\n\n").collect();
+    assert_eq!(blocks.len(), DAYS.count());
+    for (day, block) in DAYS.zip(blocks) {
+        assert_solved(day, block);
+    }
+}
+
+#[test]
+fn test_wrong_answer() {
+    let dir = Workdir::new("wrong", &[(17, "0 -> 1\n1 -> 2\n")]);
+    let output = run(&dir.0, &["17"]);
+    assert_eq!(output.code, Some(1));
+    assert_eq!(output.stderr, "");
+    assert_eq!(
+        output.stdout,
+        "🎁 Day 17: \n  Part 1: 3 ❌ (expected 492)\n  Part 2: 1 ❌ (expected 492)\n"
+    );
+}
+
+#[test]
+fn test_unknown_day() {
+    let dir = Workdir::new("unknown", &[]);
+    for (day, message) in [
+        ("42", "error: unknown day '42'\n"),
+        ("0", "error: unknown day '0'\n"),
+        ("abc", "error: unknown day 'abc'\n"),
+        (
+            "5",
+            "error: no solver for day 5: days 1 to 12 are not supported\n",
+        ),
+    ] {
+        let output = run(&dir.0, &[day]);
+        assert_eq!(output.code, Some(1), "{day}");
+        assert_eq!(output.stdout, "");
+        assert_eq!(output.stderr, message);
+    }
+}
+
+#[test]
+fn test_missing_input() {
+    let dir = Workdir::new("missing", &[]);
+    let output = run(&dir.0, &["13"]);
+    assert_eq!(output.code, Some(1));
+    assert_eq!(output.stdout, "");
+    assert_eq!(output.stderr, "error: input not found for day 13\n");
+}
+
+#[test]
+fn test_malformed_input() {
+    let dir = Workdir::new(
+        "malformed",
+        &[(14, "INP: a\nb\n"), (25, "0:\n##\n\n4x4 2\n")],
+    );
+
+    let output = run(&dir.0, &["14"]);
+    assert_eq!(output.code, Some(1));
+    assert_eq!(output.stdout, "🎁 Day 14: \n");
+    assert!(
+        output.stderr.starts_with("error: day 14: "),
+        "{}",
+        output.stderr
+    );
+    assert_eq!(output.stderr.lines().count(), 1);
+
+    // a failing day does not stop the others, but the run fails
+    let output = run(&dir.0, &[]);
+    assert_eq!(output.code, Some(1));
+    assert!(output.stdout.contains("🎁 Day 14: \n"));
+    assert!(output.stdout.contains("🎁 Day 25: \n"));
+    assert!(output.stderr.contains("error: day 14: "));
+    assert!(
+        output
+            .stderr
+            .contains("error: input not found for day 13\n")
+    );
+}
+
+#[test]
+fn test_render() {
+    let dir = Workdir::new(
+        "render",
+        &[
+            (14, "INP: abc\nabc: OUT BIN\n"),
+            (20, "(0, 0) r=2\n(3, 0) r=2\n"),
+            (23, "S1\n9E\n"),
+        ],
+    );
+
+    // the answers differ from the expected ones, but the solution is drawn anyway
+    let output = run(&dir.0, &["20", "--render"]);
+    assert_eq!(output.code, Some(1), "{}", output.stderr);
+    assert!(output.stdout.ends_with("  Rendered renders/day20-1.svg\n"));
+    let svg = fs::read_to_string(dir.0.join("renders/day20-1.svg")).unwrap();
+    assert!(svg.starts_with("<svg "));
+    assert_eq!(svg.matches("<circle ").count(), 3);
+
+    let output = run(&dir.0, &["14", "--render"]);
+    assert!(output.stdout.ends_with("  Rendered renders/day14-1.dot\n"));
+    let dot = fs::read_to_string(dir.0.join("renders/day14-1.dot")).unwrap();
+    assert!(dot.starts_with("digraph circuit {\n"));
+    assert!(dot.contains("\"abc\" -> \"BIN\" [label=\"2\"];"));
+
+    let output = run(&dir.0, &["23", "--render"]);
+    assert!(
+        output
+            .stdout
+            .ends_with("\n\x1b[1;31mS\x1b[0m\x1b[1;33m1\x1b[0m\n9\x1b[1;31mE\x1b[0m\n")
+    );
+
+    let output = run(&dir.0, &["13", "--render"]);
+    assert_eq!(output.code, Some(1));
+    assert_eq!(output.stderr, "error: input not found for day 13\n");
+}
+
+#[test]
+fn test_queries() {
+    let dir = Workdir::new("queries", &[(13, "plant 5\nquery 1\nspray odd\ntotal\n")]);
+    let output = run(&dir.0, &["13"]);
+    assert_eq!(output.code, Some(1), "{}", output.stderr);
+    assert_eq!(output.stderr, "");
+    assert!(output.stdout.starts_with(
+        "🎁 Day 13: \n\
+         \x20 plant #1: 5 (part 1), 5 (part 2)\n\
+         \x20 total: 6 (part 1), 2 (part 2)\n\
+         \x20 Part 1: 6 ❌"
+    ));
+}
+
+#[test]
+fn test_exact() {
+    let output = run_repo(&["14", "--exact", "--modulus", "1000"]);
+    assert_eq!(output.code, Some(0), "{}", output.stderr);
+    let lines: Vec<&str> = output.stdout.lines().collect();
+    assert_solved(14, &lines[..3].join("\n"));
+    assert_eq!(
+        lines[3..],
+        [
+            "  Part 2 exactly: 2726584701...9222686720 (3730 digits)",
+            "  Part 2 mod 1000: 720"
+        ]
+    );
+
+    let output = run_repo(&["13", "--exact"]);
+    assert_eq!(output.code, Some(1));
+    assert_eq!(output.stderr, "error: no exact count for day 13\n");
+
+    let output = run_repo(&["14", "--modulus", "0"]);
+    assert_eq!(output.code, Some(2));
+    assert_eq!(
+        output.stderr,
+        "error: --modulus expects a positive integer\n"
+    );
+}
+
+#[test]
+fn test_watch_options() {
+    let mut child = Command::new(BIN)
+        .args(["14", "--watch", "--exact", "--modulus", "1000"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // the first run ends with the banner of the watch
+    let mut stdout = String::new();
+    let mut reader = BufReader::new(child.stdout.take().unwrap());
+    while !stdout.contains("👀 Watching") {
+        assert_ne!(reader.read_line(&mut stdout).unwrap(), 0, "{stdout}");
+    }
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(
+        stdout.contains(
+            "\n  Part 2 exactly: 2726584701...9222686720 (3730 digits)\n  Part 2 mod 1000: 720\n"
+        ),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_explain() {
+    let output = run_repo(&["23", "--explain"]);
+    assert_eq!(output.code, Some(0), "{}", output.stderr);
+    assert_solved(23, &output.stdout);
+    assert!(
+        output
+            .stderr
+            .lines()
+            .all(|line| line.starts_with("[day 23] ")),
+        "{}",
+        output.stderr
+    );
+    assert!(
+        output
+            .stderr
+            .contains("[day 23] grid 1: part 1 minimum score = ")
+    );
+
+    let dir = Workdir::new("explain", &[(25, "0:\n###\n\n3x3: 1\n")]);
+    let output = run(&dir.0, &["25"]);
+    assert_eq!(
+        output.stderr,
+        "[day 25] warning: not all shapes have an area of 2: this solver only supports dominoes\n"
+    );
+
+    let output = run(&dir.0, &["25", "--explain=loud"]);
+    assert_eq!(output.code, Some(2));
+    assert_eq!(
+        output.stderr,
+        "error: unknown level 'loud' (warn, info or debug)\n"
+    );
+}
+
+#[test]
+fn test_inspect() {
+    let output = run_repo(&["inspect", "21"]);
+    assert_eq!(output.code, Some(0), "{}", output.stderr);
+    assert!(output.stdout.starts_with("🔎 Day 21: \n"));
+    assert!(output.stdout.ends_with("  ✅ no violated assumption\n"));
+
+    let dir = Workdir::new(
+        "inspect",
+        &[
+            (14, "INP: abc\nabc: def BIN\ndef: abc BIN\n"),
+            (25, "0:\n#\n"),
+            (17, "0 -> 1\n1 -> 20\n"),
+        ],
+    );
+    let output = run(&dir.0, &["inspect", "14"]);
+    assert_eq!(output.code, Some(1));
+    assert!(
+        output
+            .stdout
+            .contains("  ❌ OUT cannot be reached from INP: part 2 counts no pulse")
+    );
+
+    let output = run(&dir.0, &["inspect", "25"]);
+    assert_eq!(output.code, Some(1));
+    assert!(output.stdout.contains("  ❌ part 1 only supports dominoes"));
+
+    // the days without an inspector still get the statistics shared by all the inputs
+    let output = run(&dir.0, &["inspect", "17"]);
+    assert_eq!(output.code, Some(0));
+    assert!(
+        output
+            .stdout
+            .contains("  numbers       4\n  number range  0 to 20\n")
+    );
+
+    let output = run(&dir.0, &["inspect", "13"]);
+    assert_eq!(output.code, Some(1));
+    assert_eq!(output.stderr, "error: input not found for day 13\n");
+}
+
+#[test]
+fn test_batch_run() {
+    let dir = Workdir::new("batch", &[(17, "0 -> 1\n1 -> 2\n")]);
+    let chain = "0 -> 1\n1 -> 2\n";
+    for (name, content) in [
+        ("day17-alice.txt", chain),
+        ("day17-alice.answers", "3\n1\n"),
+        ("day17-bob.txt", chain),
+        ("day14-bad.txt", "INP: a\n"),
+        ("notes.txt", "not an input"),
+    ] {
+        fs::write(dir.0.join("inputs").join(name), content).unwrap();
+    }
+
+    let output = run(&dir.0, &["run", "--inputs-dir", "inputs"]);
+    assert_eq!(output.code, Some(1));
+    assert!(
+        output
+            .stdout
+            .contains("🎁 Day 17 (alice): \n  Part 1: 3 ✅\n")
+    );
+    assert!(output.stdout.contains("🎁 Day 17 (bob): \n  Part 1: 3\n"));
+    assert!(
+        output.stdout.ends_with(
+            "\
+Day  alice      bad        bob        default
+ 14  -          error      -          -
+ 17  ok         -          unchecked  wrong
+"
+        ),
+        "{}",
+        output.stdout
+    );
+    assert!(output.stderr.starts_with("error: day 14 (bad): "));
+
+    let output = run(&dir.0, &["run", "--inputs-dir", "missing"]);
+    assert_eq!(output.code, Some(1));
+    assert!(output.stderr.starts_with("error: cannot read missing: "));
+}
+
+/// Sends a GET request and returns the whole response, headers included.
+fn http_get(address: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: {address}\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_serve() {
+    let mut child = Command::new(BIN)
+        .args(["serve", "--port", "0"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut banner = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    let address = banner
+        .split("http://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap()
+        .to_string();
+
+    let page = http_get(&address, "/");
+    let days = http_get(&address, "/api/days");
+    let run = http_get(&address, "/api/run/17");
+    let missing = http_get(&address, "/api/nothing");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(page.starts_with("HTTP/1.1 200 OK\r\n"), "{page}");
+    assert!(page.contains("<a href=\"/run/17\">run</a>"));
+    assert!(days.contains("Content-Type: application/json\r\n"));
+    assert!(days.contains("{\"day\":17,\"input\":true,"));
+    assert!(
+        run.contains("\"answers\":[\"492\",\"492\"],\"queries\":[],\"correct\":[true,true]"),
+        "{run}"
+    );
+    assert!(missing.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn test_usage() {
+    let dir = Workdir::new("usage", &[]);
+    for args in [
+        &["--watch"][..],
+        &["--render"],
+        &["--exact"],
+        &["--modulus", "7"],
+        &["new", "3"],
+        &["fuzz"],
+        &["inspect", "5"],
+        &["run"],
+        &["run", "--inputs-dir"],
+        &["serve"],
+        &["serve", "--port", "http"],
+    ] {
+        let output = run(&dir.0, args);
+        assert_eq!(output.code, Some(2), "{args:?}");
+        assert!(output.stderr.starts_with("usage: "), "{args:?}");
+    }
+}